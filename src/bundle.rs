@@ -0,0 +1,283 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    input::InputSource,
+    spec::{is_external_reference, ExternalDocuments, ExternalReferenceError},
+    DiagnosticContext, HeaveError,
+};
+
+/// Combines a spec that is split across multiple files into a single document.
+///
+/// Every external reference is replaced with a local `#/components/...` reference and the value
+/// it pointed to is added to `components`. References to path items are inlined because OpenAPI
+/// 3.0 has no component type for them. References that can't be followed are left untouched and
+/// reported as diagnostics.
+pub fn bundle(
+    mut document: serde_json::Value,
+    location: &InputSource,
+) -> (serde_json::Value, Vec<HeaveError>) {
+    let mut bundler = Bundler {
+        documents: ExternalDocuments::new(Some(location)),
+        location: location.clone(),
+        bundled: HashMap::new(),
+        taken_names: HashSet::new(),
+        components: serde_json::Map::new(),
+        diagnostics: vec![],
+    };
+    bundler.register_components(&document);
+    bundler.walk(&mut document, &mut vec![]);
+
+    if !bundler.components.is_empty() {
+        if !document.get("components").is_some_and(|c| c.is_object()) {
+            document["components"] = serde_json::json!({});
+        }
+        let components = document["components"].as_object_mut().unwrap();
+        for (kind, values) in bundler.components {
+            let existing = components
+                .entry(kind)
+                .or_insert_with(|| serde_json::json!({}));
+            if let (Some(existing), serde_json::Value::Object(values)) =
+                (existing.as_object_mut(), values)
+            {
+                existing.extend(values);
+            }
+        }
+    }
+
+    (document, bundler.diagnostics)
+}
+
+struct Bundler {
+    documents: ExternalDocuments,
+    /// Where the root document was read from.
+    location: InputSource,
+    /// External references that have already been bundled, mapped to their local reference.
+    bundled: HashMap<String, String>,
+    /// `(kind, name)` pairs that are already used in `components`.
+    taken_names: HashSet<(String, String)>,
+    /// Values pulled in from other files, keyed by component kind and then by name.
+    components: serde_json::Map<String, serde_json::Value>,
+    diagnostics: Vec<HeaveError>,
+}
+
+impl Bundler {
+    /// Records the names already used in `components` so that bundled values don't replace them.
+    /// Components that are only an external reference (`Pet: {$ref: ./pet.yaml#/Pet}`) keep their
+    /// name and have the referenced value inlined into them.
+    fn register_components(&mut self, document: &serde_json::Value) {
+        let components = document.get("components").and_then(|c| c.as_object());
+        if components.is_none() {
+            return;
+        }
+        for (kind, values) in components.unwrap() {
+            let values = values.as_object();
+            if values.is_none() {
+                continue;
+            }
+            for (name, value) in values.unwrap() {
+                self.taken_names.insert((kind.clone(), name.clone()));
+                if let Some(reference) = external_reference_of(value) {
+                    let key = self.normalize_reference(reference);
+                    self.bundled
+                        .entry(key)
+                        .or_insert(format!("#/components/{}/{}", kind, name));
+                }
+            }
+        }
+    }
+
+    fn walk(&mut self, value: &mut serde_json::Value, path: &mut Vec<String>) {
+        if let Some(reference) = external_reference_of(value).map(str::to_string) {
+            self.bundle_reference(value, &reference, path);
+            return;
+        }
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, inner) in object.iter_mut() {
+                    path.push(key.clone());
+                    self.walk(inner, path);
+                    path.pop();
+                }
+            }
+            serde_json::Value::Array(array) => {
+                for (index, inner) in array.iter_mut().enumerate() {
+                    path.push(index.to_string());
+                    self.walk(inner, path);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces the external reference held by `value`, which is found at `path` in the bundled
+    /// document, with a local reference or with the value it points to.
+    fn bundle_reference(
+        &mut self,
+        value: &mut serde_json::Value,
+        reference: &str,
+        path: &[String],
+    ) {
+        let key = self.normalize_reference(reference);
+        let (file, pointer) = key.split_once('#').unwrap_or((&key, ""));
+        if self.is_root_document(file) {
+            value["$ref"] = serde_json::Value::String(format!("#{}", pointer));
+            return;
+        }
+
+        let local_pointer = format!("#{}", path_to_pointer(path));
+        let kind = component_kind(path);
+        let is_alias = self.bundled.get(&key) == Some(&local_pointer);
+        if !is_alias && kind.is_some() {
+            if let Some(local_reference) = self.bundled.get(&key) {
+                value["$ref"] = serde_json::Value::String(local_reference.clone());
+                return;
+            }
+        }
+
+        let target = match self.documents.resolve(&key) {
+            Ok(target) => target,
+            Err(e) => {
+                let context = DiagnosticContext {
+                    operation: String::new(),
+                    path: path_to_pointer(path),
+                };
+                self.diagnostics.push(match e {
+                    ExternalReferenceError::MissingFile { file, cause } => {
+                        HeaveError::MissingExternalFile {
+                            context,
+                            reference: reference.to_string(),
+                            file,
+                            cause,
+                        }
+                    }
+                    ExternalReferenceError::UnreadablePointer => {
+                        HeaveError::UnreadableExternalPointer {
+                            context,
+                            reference: reference.to_string(),
+                        }
+                    }
+                });
+                return;
+            }
+        };
+
+        if is_alias || kind.is_none() {
+            // The value is placed where the reference was, so its own references are bundled
+            // relative to the same location.
+            *value = target;
+            let mut path = path.to_vec();
+            self.walk(value, &mut path);
+            return;
+        }
+
+        let kind = kind.unwrap();
+        let name = self.unique_name(kind, &component_name(file, pointer));
+        let local_reference = format!("#/components/{}/{}", kind, name);
+        self.bundled.insert(key, local_reference.clone());
+        value["$ref"] = serde_json::Value::String(local_reference);
+
+        let mut target = target;
+        let mut target_path = vec!["components".to_string(), kind.to_string(), name.clone()];
+        self.walk(&mut target, &mut target_path);
+        self.components
+            .entry(kind)
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .unwrap()
+            .insert(name, target);
+    }
+
+    /// References in the root document are relative to it while references in other documents
+    /// have already been rebased, so both are normalized to the same form before being compared.
+    fn normalize_reference(&self, reference: &str) -> String {
+        let (_, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        format!(
+            "{}#{}",
+            ExternalDocuments::file_of(reference).display(),
+            pointer
+        )
+    }
+
+    /// Whether a file, which is relative to the root document like every normalized reference, is
+    /// the root document itself. Other files with the same name (e.g. `shared/petstore.yaml`)
+    /// aren't.
+    fn is_root_document(&self, file: &str) -> bool {
+        self.location
+            .join(Path::new(file))
+            .is_ok_and(|source| source.normalized() == self.location.normalized())
+    }
+
+    fn unique_name(&mut self, kind: &str, name: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut suffix = 2;
+        while self
+            .taken_names
+            .contains(&(kind.to_string(), candidate.clone()))
+        {
+            candidate = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+        self.taken_names
+            .insert((kind.to_string(), candidate.clone()));
+        candidate
+    }
+}
+
+fn external_reference_of(value: &serde_json::Value) -> Option<&str> {
+    value
+        .get("$ref")
+        .and_then(|r| r.as_str())
+        .filter(|r| is_external_reference(r))
+}
+
+/// Returns the `components` key a referenced value belongs under based on where the reference was
+/// found. `None` means the value has no component type and should be inlined.
+fn component_kind(path: &[String]) -> Option<&'static str> {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    if path.starts_with(&["components", "schemas"]) || path.contains(&"schema") {
+        return Some("schemas");
+    }
+    match path.as_slice() {
+        ["paths", _] => None,
+        ["components", "parameters", _] | [.., "parameters", _] => Some("parameters"),
+        ["components", "requestBodies", _] | [.., "requestBody"] => Some("requestBodies"),
+        ["components", "responses", _] | [.., "responses", _] => Some("responses"),
+        ["components", "headers", _] | [.., "headers", _] => Some("headers"),
+        ["components", "examples", _] | [.., "examples", _] => Some("examples"),
+        ["components", "links", _] | [.., "links", _] => Some("links"),
+        ["components", "callbacks", _] | [.., "callbacks", _] => Some("callbacks"),
+        ["components", "securitySchemes", _] => Some("securitySchemes"),
+        _ => Some("schemas"),
+    }
+}
+
+/// Names a bundled component after the last segment of its JSON pointer, or after the file when
+/// the whole file is referenced. Characters that aren't allowed in component names are replaced.
+fn component_name(file: &str, pointer: &str) -> String {
+    let name = match pointer.rsplit('/').next() {
+        Some(segment) if !segment.is_empty() => segment.replace("~1", "/").replace("~0", "~"),
+        _ => Path::new(file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn path_to_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
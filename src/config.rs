@@ -0,0 +1,280 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    input::{self, InputSource},
+    GenerateArgs, HeaveError,
+};
+
+/// The name of the config file that is looked for in the current directory and its parents.
+pub const CONFIG_FILE_NAME: &str = "heave.toml";
+
+/// The contents of a `heave.toml`.
+///
+/// Every option of `heave generate` can be set at the top level. Specs can also be listed in
+/// their own `[specs.<name>]` section, which overrides the top level options for that spec and is
+/// generated into the `<name>` subdirectory of the output. Relative paths are relative to the
+/// directory of the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    paths: Vec<String>,
+    output: Option<PathBuf>,
+    #[serde(default)]
+    overlays: Vec<String>,
+    template: Option<PathBuf>,
+    show_diagnostics: Option<bool>,
+    only_new: Option<bool>,
+    negative_auth: Option<bool>,
+    path_examples: Option<bool>,
+    include_paths: Option<String>,
+    include_status_codes: Option<String>,
+    include_operation_ids: Option<String>,
+    #[serde(default)]
+    specs: BTreeMap<String, SpecConfig>,
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecConfig {
+    path: String,
+    #[serde(default)]
+    overlays: Vec<String>,
+    template: Option<PathBuf>,
+    only_new: Option<bool>,
+    negative_auth: Option<bool>,
+    path_examples: Option<bool>,
+    include_paths: Option<String>,
+    include_status_codes: Option<String>,
+    include_operation_ids: Option<String>,
+}
+
+/// Everything `heave generate` needs to do, after the command line and config file have been
+/// combined.
+#[derive(Debug)]
+pub struct GeneratePlan {
+    pub output: PathBuf,
+    pub show_diagnostics: bool,
+    pub specs: Vec<SpecPlan>,
+}
+
+/// One spec to generate and the options it is generated with.
+#[derive(Debug)]
+pub struct SpecPlan {
+    pub input: InputSource,
+    /// The subdirectory of the output the spec is generated into, which is only used when there
+    /// is more than one spec.
+    pub namespace: Option<String>,
+    pub overlays: Vec<InputSource>,
+    pub template: Option<PathBuf>,
+    pub only_new: bool,
+    pub negative_auth: bool,
+    pub path_examples: bool,
+    pub include_paths: Option<String>,
+    pub include_status_codes: Option<String>,
+    pub include_operation_ids: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, HeaveError> {
+        let file = path.display().to_string();
+        let content =
+            std::fs::read_to_string(path).map_err(|e| HeaveError::ConfigParseFailure {
+                file: file.clone(),
+                line: None,
+                column: None,
+                key: String::new(),
+                message: e.to_string(),
+            })?;
+        let deserializer = toml::Deserializer::new(&content);
+        let mut config: Config = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let key = e.path().to_string();
+            let error = e.into_inner();
+            let (line, column) = match error.span() {
+                Some(span) => {
+                    let (line, column) = line_and_column(&content, span.start);
+                    (Some(line), Some(column))
+                }
+                None => (None, None),
+            };
+            HeaveError::ConfigParseFailure {
+                file: file.clone(),
+                line,
+                column,
+                key,
+                message: error.message().to_string(),
+            }
+        })?;
+        config.directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Ok(config)
+    }
+
+    /// Looks for a `heave.toml` in `directory` and then in each of its parents.
+    pub fn discover(directory: &Path) -> Option<PathBuf> {
+        directory
+            .ancestors()
+            .map(|ancestor| ancestor.join(CONFIG_FILE_NAME))
+            .find(|candidate| candidate.is_file())
+    }
+
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        self.directory.join(path)
+    }
+
+    fn resolve_source(&self, source: &str) -> Result<InputSource, Box<dyn Error>> {
+        match InputSource::from_str(source)? {
+            InputSource::File(path) => Ok(InputSource::File(self.resolve_path(&path))),
+            other => Ok(other),
+        }
+    }
+
+    fn resolve_sources(&self, sources: &[String]) -> Result<Vec<InputSource>, Box<dyn Error>> {
+        sources
+            .iter()
+            .map(|source| self.resolve_source(source))
+            .collect()
+    }
+}
+
+/// Combines the command line with the config file. Options given on the command line override
+/// the config file, and specs given on the command line replace the specs in the config file.
+///
+/// The positional arguments are the specs followed by the output directory, and either can be
+/// left out when the config file sets them. A single positional argument is the output when it
+/// is an existing directory and a spec otherwise.
+pub fn plan(args: GenerateArgs, config: Option<Config>) -> Result<GeneratePlan, Box<dyn Error>> {
+    let config = config.unwrap_or_default();
+    let mut positionals = args.inputs;
+    let cli_output = match positionals.len() {
+        0 => None,
+        1 if !Path::new(&positionals[0]).is_dir() => None,
+        _ => positionals.pop().map(PathBuf::from),
+    };
+    let output = match (cli_output, &config.output) {
+        (Some(output), _) => output,
+        (None, Some(output)) => config.resolve_path(output),
+        (None, None) => {
+            return Err(format!(
+                "An output directory is required, either as the last argument or as `output` in {}",
+                CONFIG_FILE_NAME
+            )
+            .into())
+        }
+    };
+
+    // Each spec along with the section it came from
+    let mut specs: Vec<(InputSource, Option<(&String, &SpecConfig)>)> = vec![];
+    if !positionals.is_empty() {
+        let sources = positionals
+            .iter()
+            .map(|p| InputSource::from_str(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        specs.extend(input::expand_globs(sources)?.into_iter().map(|s| (s, None)));
+    } else {
+        let sources = config.resolve_sources(&config.paths)?;
+        specs.extend(input::expand_globs(sources)?.into_iter().map(|s| (s, None)));
+        for (name, section) in config.specs.iter() {
+            specs.push((config.resolve_source(&section.path)?, Some((name, section))));
+        }
+    }
+    if specs.is_empty() {
+        return Err(format!(
+            "No input specs were given, either as arguments or in {}",
+            CONFIG_FILE_NAME
+        )
+        .into());
+    }
+
+    let cli_overlays = args.overlays;
+    let is_namespaced = specs.len() > 1;
+    let inputs: Vec<InputSource> = specs.iter().map(|(input, _)| input.clone()).collect();
+    let namespaces = input::namespaces(&inputs);
+    let mut spec_plans = vec![];
+    for ((input, section), namespace) in specs.into_iter().zip(namespaces) {
+        let name = section.map(|(name, _)| name.clone());
+        let section = section.map(|(_, section)| section);
+        let overlays = if !cli_overlays.is_empty() {
+            cli_overlays.clone()
+        } else {
+            let mut overlays = config.resolve_sources(&config.overlays)?;
+            if let Some(section) = section {
+                overlays.extend(config.resolve_sources(&section.overlays)?);
+            }
+            overlays
+        };
+        let template = args.template.clone().or_else(|| {
+            section
+                .and_then(|s| s.template.as_ref())
+                .or(config.template.as_ref())
+                .map(|t| config.resolve_path(t))
+        });
+        spec_plans.push(SpecPlan {
+            input,
+            namespace: is_namespaced.then(|| name.unwrap_or(namespace)),
+            overlays,
+            template,
+            only_new: flag(args.only_new, args.no_only_new)
+                .or(section.and_then(|s| s.only_new))
+                .or(config.only_new)
+                .unwrap_or(false),
+            negative_auth: flag(args.negative_auth, args.no_negative_auth)
+                .or(section.and_then(|s| s.negative_auth))
+                .or(config.negative_auth)
+                .unwrap_or(false),
+            path_examples: flag(args.path_examples, args.no_path_examples)
+                .or(section.and_then(|s| s.path_examples))
+                .or(config.path_examples)
+                .unwrap_or(false),
+            include_paths: args.include_paths.clone().or_else(|| {
+                section
+                    .and_then(|s| s.include_paths.clone())
+                    .or(config.include_paths.clone())
+            }),
+            include_status_codes: args.include_status_codes.clone().or_else(|| {
+                section
+                    .and_then(|s| s.include_status_codes.clone())
+                    .or(config.include_status_codes.clone())
+            }),
+            include_operation_ids: args.include_operation_ids.clone().or_else(|| {
+                section
+                    .and_then(|s| s.include_operation_ids.clone())
+                    .or(config.include_operation_ids.clone())
+            }),
+        });
+    }
+
+    Ok(GeneratePlan {
+        output,
+        show_diagnostics: flag(args.show_diagnostics, args.no_show_diagnostics)
+            .or(config.show_diagnostics)
+            .unwrap_or(false),
+        specs: spec_plans,
+    })
+}
+
+/// The value of a flag given as `--<flag>` or `--no-<flag>`, or `None` when neither was given and
+/// the config file decides. Only the last of the two is set when both are given.
+fn flag(enabled: bool, disabled: bool) -> Option<bool> {
+    match (enabled, disabled) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Converts a byte offset into the 1-based line and column it is at.
+fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
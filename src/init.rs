@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use crate::{
+    config::CONFIG_FILE_NAME,
+    generate,
+    spec::Spec,
+    variables::{self, VARIABLES_FILE},
+    DEFAULT_HURL_TEMPLATE,
+};
+
+/// Where `heave init` puts the copy of the default template, relative to the project.
+const TEMPLATE_FILE: &str = "templates/default.hurl";
+
+/// The directory `heave init` configures the generated files to be written to.
+pub const OUTPUT_DIRECTORY: &str = "hurl";
+
+/// Returns the files that `heave init` writes for a spec, relative to the project directory,
+/// along with their content. `spec_location` is how the config file refers to the spec.
+pub fn scaffold(spec_location: &str, mut spec: Spec) -> Vec<(PathBuf, String)> {
+    let config = format!(
+        r#"# Options for `heave generate`. Options given on the command line override these.
+paths = [{spec}]
+output = "{output}"
+template = "{template}"
+show_diagnostics = false
+only_new = false
+negative_auth = false
+path_examples = false
+# include_paths = ""
+# include_status_codes = ""
+# include_operation_ids = ""
+"#,
+        spec = toml_string(spec_location),
+        output = OUTPUT_DIRECTORY,
+        template = TEMPLATE_FILE,
+    );
+
+    let outputs = generate(&mut spec).outputs;
+    let variables = variables::render(VARIABLES_FILE, &variables::collect(&spec, &outputs));
+
+    let mut files = vec![
+        (PathBuf::from(CONFIG_FILE_NAME), config),
+        (
+            PathBuf::from(TEMPLATE_FILE),
+            DEFAULT_HURL_TEMPLATE.to_string(),
+        ),
+        (PathBuf::from(VARIABLES_FILE), variables),
+    ];
+    for (file, variables) in variables::environments(&spec, &outputs) {
+        let content = variables::render(&file, &variables);
+        files.push((PathBuf::from(file), content));
+    }
+    files
+}
+
+/// Quotes a value as a TOML basic string.
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
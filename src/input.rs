@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use url::Url;
+
+use crate::spec::normalize_path;
+
+/// Where a spec is read from. References to other files are resolved relative to it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    File(PathBuf),
+    /// References in a spec read from stdin are resolved relative to the current directory.
+    Stdin,
+    Url(Url),
+}
+
+impl InputSource {
+    pub fn read(&self) -> Result<String, Box<dyn Error>> {
+        match self {
+            InputSource::File(path) => {
+                let metadata = std::fs::metadata(path)?;
+                if !metadata.is_file() {
+                    return Err("Input spec must be a file".into());
+                }
+                Ok(std::fs::read_to_string(path)?)
+            }
+            InputSource::Stdin => {
+                let mut content = String::new();
+                std::io::stdin().read_to_string(&mut content)?;
+                Ok(content)
+            }
+            InputSource::Url(url) => Ok(ureq::get(url.as_str()).call()?.into_string()?),
+        }
+    }
+
+    /// Returns the source of a file referenced from this one. `file` is either relative to this
+    /// source or an absolute URL.
+    pub fn join(&self, file: &Path) -> Result<InputSource, Box<dyn Error>> {
+        let file_str = file.to_string_lossy();
+        if is_url(&file_str) {
+            return Ok(InputSource::Url(Url::parse(&file_str)?));
+        }
+        match self {
+            InputSource::File(path) => Ok(InputSource::File(
+                path.parent().unwrap_or(Path::new("")).join(file),
+            )),
+            InputSource::Stdin => Ok(InputSource::File(file.to_path_buf())),
+            InputSource::Url(url) => Ok(InputSource::Url(url.join(&file_str)?)),
+        }
+    }
+
+    /// The name of the file without the directory.
+    pub fn file_name(&self) -> Option<PathBuf> {
+        match self {
+            InputSource::File(path) => path.file_name().map(PathBuf::from),
+            InputSource::Stdin => None,
+            InputSource::Url(url) => url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|segment| !segment.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+
+    /// The same source with `.` and `..` removed from file paths, so that different paths to the
+    /// same file are equal. URLs are already normalized when they are parsed or joined.
+    pub fn normalized(&self) -> InputSource {
+        match self {
+            InputSource::File(path) => InputSource::File(normalize_path(path)),
+            other => other.clone(),
+        }
+    }
+
+    /// The name of the file without its extension.
+    fn stem(&self) -> String {
+        let stem = match self {
+            InputSource::File(path) => path.file_stem().map(|s| s.to_string_lossy().to_string()),
+            InputSource::Stdin => Some("stdin".to_string()),
+            InputSource::Url(url) => self
+                .file_name()
+                .and_then(|name| name.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .or_else(|| url.host_str().map(str::to_string)),
+        };
+        stem.unwrap_or_default()
+    }
+
+    /// The name of the directory the spec is in.
+    fn parent_name(&self) -> Option<String> {
+        match self {
+            InputSource::File(path) => path
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().to_string()),
+            InputSource::Stdin => None,
+            InputSource::Url(url) => {
+                let mut segments: Vec<&str> = url.path_segments()?.collect();
+                segments.pop();
+                segments
+                    .pop()
+                    .filter(|segment| !segment.is_empty())
+                    .map(str::to_string)
+            }
+        }
+    }
+}
+
+impl FromStr for InputSource {
+    type Err = url::ParseError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "-" {
+            return Ok(InputSource::Stdin);
+        }
+        if is_url(value) {
+            return Ok(InputSource::Url(Url::parse(value)?));
+        }
+        Ok(InputSource::File(PathBuf::from(value)))
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::File(path) => write!(f, "{}", path.display()),
+            InputSource::Stdin => write!(f, "<stdin>"),
+            InputSource::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// Expands sources that are glob patterns (like `services/*/openapi.yaml`) into the files they
+/// match, for shells that don't expand them and for patterns that were quoted. A pattern that
+/// doesn't match any files is an error.
+pub fn expand_globs(sources: Vec<InputSource>) -> Result<Vec<InputSource>, Box<dyn Error>> {
+    let mut expanded = vec![];
+    for source in sources {
+        let pattern = match &source {
+            InputSource::File(path) if !path.exists() && is_glob(&path.to_string_lossy()) => {
+                path.to_string_lossy().to_string()
+            }
+            _ => {
+                expanded.push(source);
+                continue;
+            }
+        };
+        let mut matches = glob::glob(&pattern)?.collect::<Result<Vec<PathBuf>, _>>()?;
+        if matches.is_empty() {
+            return Err(format!("No input specs match {}", pattern).into());
+        }
+        matches.sort();
+        expanded.extend(matches.into_iter().map(InputSource::File));
+    }
+    Ok(expanded)
+}
+
+/// Names the subdirectory of the output that each spec is generated into. Specs are named after
+/// their file without the extension, or after the directory they are in when several specs share
+/// a file name (like `users/openapi.yaml` and `orders/openapi.yaml`).
+pub fn namespaces(sources: &[InputSource]) -> Vec<String> {
+    let stems: Vec<String> = sources.iter().map(|source| source.stem()).collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for stem in stems.iter() {
+        *counts.entry(stem).or_default() += 1;
+    }
+    let names: Vec<String> = sources
+        .iter()
+        .zip(stems.iter())
+        .map(|(source, stem)| match counts[stem.as_str()] {
+            1 => stem.clone(),
+            _ => source.parent_name().unwrap_or_else(|| stem.clone()),
+        })
+        .collect();
+
+    // Anything that still collides is told apart by its position
+    let mut taken = HashMap::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let count: &mut usize = taken.entry(name.clone()).or_default();
+            *count += 1;
+            match *count {
+                1 => name,
+                count => format!("{}_{}", name, count),
+            }
+        })
+        .collect()
+}
+
+fn is_glob(value: &str) -> bool {
+    value.contains(['*', '?', '['])
+}
+
+/// Returns true for `http://` and `https://` URLs, which are fetched instead of read from disk.
+pub fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
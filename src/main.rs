@@ -1,12 +1,30 @@
 use clap::{Args, Parser, Subcommand};
+use config::Config;
+use input::InputSource;
 use itertools::Itertools;
 use minijinja::{context, Environment};
-use openapiv3::{MediaType, OpenAPI, ReferenceOr};
+use openapiv3::{MediaType, ReferenceOr};
+use overlay::Overlay;
+use security::Authentication;
+use spec::{ExternalReferenceError, Spec};
 use std::{
+    borrow::Cow,
     error::Error,
     path::{Path, PathBuf},
 };
 
+mod bundle;
+mod config;
+mod init;
+mod input;
+mod openapi31;
+mod overlay;
+mod parse;
+mod security;
+mod spec;
+mod swagger2;
+mod variables;
+
 /// Program to generate hurl files from openapi schemas
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -22,30 +40,124 @@ enum Commands {
 
     #[command(about = "Print the default template")]
     Template,
+
+    #[command(about = "Combine an OpenAPI spec split across multiple files into a single file")]
+    Bundle(BundleArgs),
+
+    #[command(
+        about = "Create a heave.toml, a copy of the default template and a hurl variables file for a spec"
+    )]
+    Init(InitArgs),
+}
+
+#[derive(Args, Debug)]
+struct InitArgs {
+    #[arg(help = "The path or HTTP(S) URL of the OpenAPI spec to generate from\n")]
+    path: InputSource,
+
+    #[arg(
+        long,
+        default_value = ".",
+        help = "The directory to create the files in. Files that already exist are left untouched\n"
+    )]
+    directory: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct BundleArgs {
+    #[arg(
+        help = "The path or HTTP(S) URL of an OpenAPI spec, or - to read it from stdin. References to other files are resolved relative to this spec\n"
+    )]
+    path: InputSource,
+
+    #[arg(
+        short,
+        long,
+        help = "The file where the bundled spec will be written. The spec is written as json when the file ends in `.json` and as yaml otherwise. Prints to stdout when not provided\n"
+    )]
+    output: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
+#[command(override_usage = "heave generate [OPTIONS] [SPEC]... [OUTPUT]")]
 struct GenerateArgs {
     #[arg(
-        help = "The path to an OpenAPI spec. This spec must not contain references to other files\n"
+        value_name = "SPEC... OUTPUT",
+        help = "The paths, globs or HTTP(S) URLs of OpenAPI specs, or - to read one from stdin, followed by the directory where generated hurl files will be created. Either can be left out when they are set in heave.toml. References to other files are resolved relative to each spec. When there is more than one spec, each is generated into a subdirectory of the output named after the spec\n"
+    )]
+    inputs: Vec<String>,
+
+    #[arg(
+        long,
+        help = "The config file to read options from. Defaults to the first heave.toml found in the current directory or its parents. Options given on the command line override the config file\n"
     )]
-    path: PathBuf,
+    config: Option<PathBuf>,
 
-    #[arg(help = "The directory where generated hurl files will be created\n")]
-    output: PathBuf,
+    #[arg(
+        long = "overlay",
+        help = "An OpenAPI Overlay 1.0 document to apply to the spec before generating. Can be repeated, overlays are applied in order\n"
+    )]
+    overlays: Vec<InputSource>,
 
     #[arg(long, help = "Prints the default template\n")]
     template: Option<PathBuf>,
 
-    #[arg(long, help = "Prints diagnostics to stdout\n")]
+    #[arg(
+        long,
+        overrides_with = "no_show_diagnostics",
+        help = "Prints diagnostics to stdout\n"
+    )]
     show_diagnostics: bool,
 
     #[arg(
         long,
+        overrides_with = "show_diagnostics",
+        help = "Doesn't print diagnostics, even when the config file sets show_diagnostics\n"
+    )]
+    no_show_diagnostics: bool,
+
+    #[arg(
+        long,
+        overrides_with = "no_only_new",
         help = "Only generate new files, do not overwrite existing files\n"
     )]
     only_new: bool,
 
+    #[arg(
+        long,
+        overrides_with = "only_new",
+        help = "Overwrites existing files, even when the config file sets only_new\n"
+    )]
+    no_only_new: bool,
+
+    #[arg(
+        long,
+        overrides_with = "no_negative_auth",
+        help = "Also generate files without credentials and with invalid credentials for the 401 and 403 responses of operations that require authentication\n"
+    )]
+    negative_auth: bool,
+
+    #[arg(
+        long,
+        overrides_with = "negative_auth",
+        help = "Doesn't generate the files without credentials or with invalid credentials, even when the config file sets negative_auth\n"
+    )]
+    no_negative_auth: bool,
+
+    #[arg(
+        long,
+        overrides_with = "no_path_examples",
+        help = "Puts the example or default value of path parameters into the path instead of a hurl variable. Parameters without one are left as a variable\n"
+    )]
+    path_examples: bool,
+
+    #[arg(
+        long,
+        overrides_with = "path_examples",
+        help = "Keeps path parameters as hurl variables, even when the config file sets path_examples\n"
+    )]
+    no_path_examples: bool,
+
     #[arg(
         long,
         help = r#"A regex to match against paths in the OpenAPI spec. Only paths that match will be included in the generated files.
@@ -98,10 +210,31 @@ pub struct Output {
     pub oas_path: String,
     pub oas_operation_id: Option<String>,
     pub method: String,
+    pub path_parameters: Vec<PathParameter>,
+    /// The variable holding the base URL, which differs from `baseurl` when the operation or its
+    /// path overrides the servers of the spec.
+    pub base_url_variable: String,
     pub header_parameters: Vec<String>,
     pub query_parameters: Vec<String>,
+    pub cookie_parameters: Vec<String>,
+    /// The credentials required by the operation's security schemes.
+    pub authentication: Authentication,
     pub asserts: Vec<String>,
     pub request_body_parameter: String,
+    pub request_body_variant: Option<String>,
+    pub response_body_variant: Option<String>,
+}
+
+/// A parameter that is part of the path, e.g. `petId` in `/pets/{petId}`.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PathParameter {
+    pub name: String,
+    /// The type of the parameter's schema (e.g. `integer`), if it has one.
+    pub schema_type: Option<String>,
+    pub required: bool,
+    pub description: Option<String>,
+    /// The parameter's `example`, or its schema's `example`, `default` or first `enum` value.
+    pub example: Option<String>,
 }
 
 #[derive(Debug)]
@@ -110,19 +243,77 @@ pub struct GenerateResult {
     diagnostics: Vec<HeaveError>,
 }
 
-pub enum InputSpecExtension {
+/// A single request body that should be sent for an operation.
+///
+/// Most operations only have one variant without a name. Request bodies using `oneOf` produce one
+/// named variant per branch so that each branch can be written to its own file.
+#[derive(Debug)]
+struct RequestBodyVariant {
+    name: Option<String>,
+    body: Option<String>,
+}
+
+/// The asserts generated for a single response.
+///
+/// Responses using a oneOf or anyOf with a discriminator produce one named variant per branch.
+/// Every other response has a single variant without a name.
+#[derive(Debug)]
+struct ResponseBodyVariant {
+    name: Option<String>,
+    asserts: Vec<String>,
+}
+
+/// A resolved branch of a oneOf or anyOf schema.
+struct SchemaVariant<'a> {
+    name: String,
+    schema: Cow<'a, openapiv3::Schema>,
+    discriminator_value: Option<String>,
+}
+
+pub enum InputSpecFormat {
     Json,
     Yaml,
 }
 
-const DEFAULT_HURL_TEMPLATE: &str = r#"{{ method }} {{ '{{ baseurl }}' }}{{ path | safe }}
-Authorization: Bearer {{ '{{ authorization }}' }}
-Prefer: code={{ expected_status_code }}
+impl InputSpecFormat {
+    /// Works out the format from the content rather than the file extension, which means specs
+    /// can be read from anywhere, including stdin.
+    fn sniff(content: &str) -> Self {
+        if content.trim_start().starts_with('{') {
+            InputSpecFormat::Json
+        } else {
+            InputSpecFormat::Yaml
+        }
+    }
+}
+
+const DEFAULT_HURL_TEMPLATE: &str = r#"{% for token_request in authentication.token_requests %}POST {{ token_request.url }}
+[FormParams]
+grant_type: client_credentials
+client_id: {{ token_request.client_id }}
+client_secret: {{ token_request.client_secret }}
+{% if token_request.scope %}scope: {{ token_request.scope }}
+{% endif %}
+HTTP 200
+[Captures]
+{{ token_request.capture }}: jsonpath "$.access_token"
+
+{% endfor %}{{ method }} {{ '{{ ' ~ base_url_variable ~ ' }}' }}{{ path | safe }}
+{% for header in authentication.headers %}{{ header.name }}: {{ header.value }}
+{% endfor %}Prefer: code={{ expected_status_code }}
 {% for header in header_parameters %}{{ header }}:
-{% endfor %}{% if query_parameters %}
+{% endfor %}{% if query_parameters or authentication.query_parameters %}
 [QueryStringParams]
-{% for query in query_parameters %}{{ query }}:
-{% endfor %}
+{% for query in authentication.query_parameters %}{{ query.name }}: {{ query.value }}
+{% endfor %}{% for query in query_parameters %}{{ query }}:
+{% endfor %}{% endif %}{% if cookie_parameters or authentication.cookies %}
+[Cookies]
+{% for cookie in authentication.cookies %}{{ cookie.name }}: {{ cookie.value }}
+{% endfor %}{% for cookie in cookie_parameters %}{{ cookie }}:
+{% endfor %}{% endif %}{% if authentication.basic_auth %}
+[BasicAuth]
+{{ authentication.basic_auth.name }}: {{ authentication.basic_auth.value }}
+{% endif %}{% if query_parameters or authentication.query_parameters or cookie_parameters or authentication.cookies or authentication.basic_auth %}
 {% endif %}{{ request_body_parameter }}
 HTTP {{ expected_status_code }}
 {% if asserts %}
@@ -143,7 +334,7 @@ pub enum HeaveError {
 ---------------------------
 MalformedParameterReference
 
-Message: Parameter references must be start with `#/components/parameters/`.
+Message: Parameter references must be valid JSON pointers (RFC 6901).
 Path: {path}
 Operation: {operation}
 Reference: {reference}"#
@@ -178,7 +369,7 @@ Reference: {}"#, .context.path, .context.operation, .reference
 -----------------------------
 MalformedRequestBodyReference
 
-Message: RequestBody references must be start with `#/components/requestBodies/`.
+Message: RequestBody references must be valid JSON pointers (RFC 6901).
 Path: {}
 Operation: {}
 Reference: {}"#, .context.path, .context.operation, .reference
@@ -201,21 +392,6 @@ Reference: {}"#, .context.path, .context.operation, .reference
         context: DiagnosticContext,
         reference: String,
     },
-    // TODO maybe this should be allowed?
-    #[error(
-        r#"
-----------------------------
-FailedRequestBodyDereference
-
-Message: RequestBodies defined in `#/components/requestBodies/` must not contain references.
-Path: {}
-Operation: {}
-Reference: {}"#, .context.path, .context.operation, .reference
-    )]
-    FailedRequestBodyDereference {
-        context: DiagnosticContext,
-        reference: String,
-    },
     #[error(
         r#"
 ------------------------------------------
@@ -241,7 +417,7 @@ Operation: {}"#, .context.path, .context.operation,
 ------------------------
 MalformedSchemaReference
 
-Message: Schema references must be start with `#/components/schemas/`.
+Message: Schema references must be valid JSON pointers (RFC 6901).
 Path: {}
 Operation: {}
 Reference: {}"#, .context.path, .context.operation, .reference
@@ -264,27 +440,12 @@ Reference: {}"#, .context.path, .context.operation, .reference
         context: DiagnosticContext,
         reference: String,
     },
-    // TODO maybe this should be allowed?
-    #[error(
-        r#"
------------------------
-FailedSchemaDereference
-
-Message: Schemas defined in `#/components/schemas/` must not contain references.
-Path: {}
-Operation: {}
-Reference: {}"#, .context.path, .context.operation, .reference
-    )]
-    FailedSchemaDereference {
-        context: DiagnosticContext,
-        reference: String,
-    },
     #[error(
         r#"
 -------------------------------------------
 UnsupportedSchemaKind
 
-Message: Generation based on schemas using AnyOf, OneOf, Not, or Any are not currently supported.
+Message: Generation based on response schemas using OneOf without a discriminator, or request body schemas using Not, is not currently supported.
 Path: {}
 Operation: {}
 Detected Kind: {}
@@ -309,10 +470,69 @@ Operation: {}"#,
     UnsupportedStatusCodeRange { context: DiagnosticContext },
     #[error(
         r#"
+----------------
+SkippedParameter
+
+Message: {}
+Path: {}
+Operation: {}
+Parameter: {}
+Location: {}"#, .reason, .context.path, .context.operation, .parameter, .location
+    )]
+    SkippedParameter {
+        context: DiagnosticContext,
+        parameter: String,
+        location: String,
+        reason: String,
+    },
+    #[error(
+        r#"
+---------------------
+MissingSecurityScheme
+
+Message: The security requirement names a scheme that isn't defined in `components.securitySchemes`.
+Path: {}
+Operation: {}
+Scheme: {}"#, .context.path, .context.operation, .scheme
+    )]
+    MissingSecurityScheme {
+        context: DiagnosticContext,
+        scheme: String,
+    },
+    #[error(
+        r#"
+---------------------------------
+MalformedSecuritySchemeReference
+
+Message: SecurityScheme references must be valid JSON pointers (RFC 6901).
+Path: {}
+Operation: {}
+Reference: {}"#, .context.path, .context.operation, .reference
+    )]
+    MalformedSecuritySchemeReference {
+        context: DiagnosticContext,
+        reference: String,
+    },
+    #[error(
+        r#"
+-------------------------------
+MissingSecuritySchemeReference
+
+Message: Failed to find SecurityScheme reference.
+Path: {}
+Operation: {}
+Reference: {}"#, .context.path, .context.operation, .reference
+    )]
+    MissingSecuritySchemeReference {
+        context: DiagnosticContext,
+        reference: String,
+    },
+    #[error(
+        r#"
 ------------------------------
 MalformedResponseBodyReference
 
-Message: Response references must be start with `#/components/responses/`.
+Message: Response references must be valid JSON pointers (RFC 6901).
 Path: {}
 Operation: {}
 Reference: {}"#, .context.path, .context.operation, .reference
@@ -335,23 +555,54 @@ Reference: {}"#, .context.path, .context.operation, .reference
         context: DiagnosticContext,
         reference: String,
     },
-    // TODO maybe this should be allowed?
     #[error(
         r#"
------------------------------
-FailedResponseBodyDereference
+-------------------
+MissingExternalFile
+
+Message: Failed to load the file a reference points to.
+Path: {}
+Operation: {}
+Reference: {}
+File: {}
+Cause: {}"#, .context.path, .context.operation, .reference, .file, .cause
+    )]
+    MissingExternalFile {
+        context: DiagnosticContext,
+        reference: String,
+        file: String,
+        cause: String,
+    },
+    #[error(
+        r#"
+------------------------
+UnreadableExternalPointer
 
-Message: Schemas defined in `#/components/responses/` must not contain references.
+Message: The JSON pointer of an external reference does not point to a valid definition.
 Path: {}
 Operation: {}
 Reference: {}"#, .context.path, .context.operation, .reference
     )]
-    FailedResponseBodyDereference {
+    UnreadableExternalPointer {
         context: DiagnosticContext,
         reference: String,
     },
     #[error(
         r#"
+-----------------
+CircularReference
+
+Message: A chain of references leads back to a reference that was already visited.
+Path: {}
+Operation: {}
+References: {}"#, .context.path, .context.operation, .references
+    )]
+    CircularReference {
+        context: DiagnosticContext,
+        references: String,
+    },
+    #[error(
+        r#"
 ----------------------------
 Malformed --include-paths Regex
 
@@ -379,6 +630,69 @@ Source: {}"#, .source
     MalformedIncludeOperationIDsRegex { source: regex_lite::Error },
     #[error(
         r#"
+----------------------
+LossySwaggerConversion
+
+Message: {message}
+Location: {location}"#
+    )]
+    LossySwaggerConversion { location: String, message: String },
+    #[error(
+        r#"
+----------------------
+UnmatchedOverlayTarget
+
+Message: The target of an overlay action did not select anything in the spec.
+File: {file}
+Target: {target}"#
+    )]
+    UnmatchedOverlayTarget { file: String, target: String },
+    #[error(
+        r#"
+------------------
+ConfigParseFailure
+
+Message: {}
+File: {}
+Line: {}
+Column: {}
+Key: {}"#,
+.message, .file,
+.line.map_or("unknown".to_string(), |l| l.to_string()),
+.column.map_or("unknown".to_string(), |c| c.to_string()),
+.key
+    )]
+    ConfigParseFailure {
+        file: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        key: String,
+        message: String,
+    },
+    #[error(
+        r#"
+----------------
+SpecParseFailure
+
+Message: {}
+File: {}
+Line: {}
+Column: {}
+JSON path: {}"#,
+.message, .file,
+.line.map_or("unknown".to_string(), |l| l.to_string()),
+.column.map_or("unknown".to_string(), |c| c.to_string()),
+.jsonpath
+    )]
+    SpecParseFailure {
+        file: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        jsonpath: String,
+        message: String,
+    },
+    #[error(
+        r#"
 -----------------------------
 Request Body Schema Cycle Detected
 
@@ -417,136 +731,178 @@ fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
     match cli.command {
         Commands::Generate(args) => {
-            if args.include_paths.is_some() {
-                let include_paths = args.include_paths.as_ref().unwrap();
-                let valid = regex_lite::Regex::new(include_paths)
-                    .map_err(|e| HeaveError::MalformedIncludePathsRegex { source: e });
-                if valid.is_err() {
-                    let valid = valid.unwrap_err();
-                    println!("{}", valid);
-                    return Err(valid.into());
+            let config_path = match &args.config {
+                Some(path) => Some(path.clone()),
+                None => Config::discover(&std::env::current_dir()?),
+            };
+            let config = match config_path.map(|path| Config::load(&path)) {
+                Some(Err(e)) => {
+                    println!("{}", e);
+                    return Err("Config file could not be parsed".into());
                 }
-            }
-            if args.include_status_codes.is_some() {
-                let include_status_codes = args.include_status_codes.as_ref().unwrap();
-                let valid = regex_lite::Regex::new(include_status_codes)
-                    .map_err(|e| HeaveError::MalformedIncludeStatusCodesRegex { source: e });
-                if valid.is_err() {
-                    let valid = valid.unwrap_err();
-                    println!("{}", valid);
-                    return Err(valid.into());
+                Some(Ok(config)) => Some(config),
+                None => None,
+            };
+            let plan = config::plan(args, config)?;
+
+            for spec in plan.specs.iter() {
+                if let Some(include_paths) = &spec.include_paths {
+                    let valid = regex_lite::Regex::new(include_paths)
+                        .map_err(|e| HeaveError::MalformedIncludePathsRegex { source: e });
+                    if let Err(valid) = valid {
+                        println!("{}", valid);
+                        return Err(valid.into());
+                    }
+                }
+                if let Some(include_status_codes) = &spec.include_status_codes {
+                    let valid = regex_lite::Regex::new(include_status_codes)
+                        .map_err(|e| HeaveError::MalformedIncludeStatusCodesRegex { source: e });
+                    if let Err(valid) = valid {
+                        println!("{}", valid);
+                        return Err(valid.into());
+                    }
                 }
-            }
 
-            if args.include_operation_ids.is_some() {
-                let include_operation_ids = args.include_operation_ids.as_ref().unwrap();
-                let valid = regex_lite::Regex::new(include_operation_ids)
-                    .map_err(|e| HeaveError::MalformedIncludeOperationIDsRegex { source: e });
-                if valid.is_err() {
-                    let valid = valid.unwrap_err();
-                    println!("{}", valid);
-                    return Err(valid.into());
+                if let Some(include_operation_ids) = &spec.include_operation_ids {
+                    let valid = regex_lite::Regex::new(include_operation_ids)
+                        .map_err(|e| HeaveError::MalformedIncludeOperationIDsRegex { source: e });
+                    if let Err(valid) = valid {
+                        println!("{}", valid);
+                        return Err(valid.into());
+                    }
                 }
             }
 
-            let output_directory = args.output;
+            let output_directory = plan.output;
             let output_directory_metadata = std::fs::metadata(&output_directory)?;
             if !output_directory_metadata.is_dir() {
                 return Err("Output must be a directory".into());
             }
 
-            let template = match &args.template {
-                Some(t) => {
-                    let metadata = std::fs::metadata(t)?;
-                    if !metadata.is_file() {
-                        return Err("Template must be a file".into());
+            let mut has_parse_failures = false;
+            let mut has_diagnostics = false;
+            for spec_plan in plan.specs {
+                let template = match &spec_plan.template {
+                    Some(t) => {
+                        let metadata = std::fs::metadata(t)?;
+                        if !metadata.is_file() {
+                            return Err("Template must be a file".into());
+                        }
+                        let template_content = std::fs::read_to_string(t);
+                        template_content.unwrap()
+                    }
+                    None => DEFAULT_HURL_TEMPLATE.to_string(),
+                };
+
+                // This is used as a mechanism to validate that the syntax of the template parses
+                // correctly before doing more work. The function that writes the output creates
+                // its own minijinja Environment.
+                let mut jinja_env = Environment::new();
+                jinja_env
+                    .add_template("output.hurl", &template)
+                    .map_err(|e| HeaveError::JinjaError { source: e })?;
+
+                let mut overlays = vec![];
+                for location in spec_plan.overlays.iter() {
+                    let overlay = Overlay::load(&location.read()?, location);
+                    if let Err(e) = overlay {
+                        println!("{}", e);
+                        return Err("Overlay could not be parsed".into());
                     }
-                    let template_content = std::fs::read_to_string(t);
-                    template_content.unwrap()
+                    overlays.push(overlay.unwrap());
                 }
-                None => DEFAULT_HURL_TEMPLATE.to_string(),
-            };
-
-            // This is used as a mechanism to validate that the syntax of the template parses
-            // correctly before doing more work. The function that writes the output creates its
-            // own minijinja Environment.
-            let mut jinja_env = Environment::new();
-            jinja_env
-                .add_template("output.hurl", &template)
-                .map_err(|e| HeaveError::JinjaError { source: e })?;
-
-            let input_path = &args.path;
-            let input_metadata = std::fs::metadata(input_path)?;
-            if !input_metadata.is_file() {
-                return Err("Input spec must be a file".into());
-            }
-            let input_extension = match &input_path.extension() {
-                Some(ext) => match ext.to_str() {
-                    Some("json") => Ok(InputSpecExtension::Json),
-                    Some("yaml") => Ok(InputSpecExtension::Yaml),
-                    _ => Err("Input spec must be json or yaml file"),
-                },
-                None => Err("Input spec must be json or yaml file"),
-            }?;
 
-            let content = std::fs::read_to_string(input_path)?;
-            let openapi: OpenAPI = match input_extension {
-                InputSpecExtension::Json => {
-                    serde_json::from_str(&content).expect("Could not deserialize input as json")
+                let input = &spec_plan.input;
+                let content = input.read()?;
+                let spec = Spec::load(&content, input, &overlays);
+                if let Err(e) = spec {
+                    println!("{}", e);
+                    has_parse_failures = true;
+                    continue;
                 }
-                InputSpecExtension::Yaml => {
-                    serde_yaml::from_str(&content).expect("Could not deserialize input as yaml")
+                let mut spec = spec.unwrap();
+                let result = generate(&mut spec);
+                let mut final_outputs = result.outputs;
+                if spec_plan.negative_auth {
+                    final_outputs = security::add_negative_outputs(final_outputs);
+                }
+                if spec_plan.path_examples {
+                    final_outputs = substitute_path_examples(final_outputs);
+                }
+                if let Some(include_paths) = spec_plan.include_paths {
+                    // Regex was validated at the start of the CLI
+                    let regex = regex_lite::Regex::new(&include_paths).unwrap();
+                    final_outputs = filter_include_paths_outputs(regex, final_outputs);
                 }
-            };
 
-            let result = generate(openapi);
-            let mut final_outputs = result.outputs;
-            if args.include_paths.is_some() {
-                let include_paths = args.include_paths.unwrap();
-                // Regex was validated at the start of the CLI
-                let regex = regex_lite::Regex::new(&include_paths).unwrap();
-                final_outputs = filter_include_paths_outputs(regex, final_outputs);
-            }
+                if let Some(include_status_codes) = spec_plan.include_status_codes {
+                    // Regex was validated at the start of the CLI
+                    let regex = regex_lite::Regex::new(&include_status_codes).unwrap();
+                    final_outputs = filter_include_status_codes_outputs(regex, final_outputs);
+                }
 
-            if args.include_status_codes.is_some() {
-                let include_status_codes = args.include_status_codes.unwrap();
-                // Regex was validated at the start of the CLI
-                let regex = regex_lite::Regex::new(&include_status_codes).unwrap();
-                final_outputs = filter_include_status_codes_outputs(regex, final_outputs);
-            }
+                if let Some(include_operation_ids) = spec_plan.include_operation_ids {
+                    // Regex was validated at the start of the CLI
+                    let regex = regex_lite::Regex::new(&include_operation_ids).unwrap();
+                    final_outputs = filter_include_operation_ids_outputs(regex, final_outputs);
+                }
 
-            if args.include_operation_ids.is_some() {
-                let include_operation_ids = args.include_operation_ids.unwrap();
-                // Regex was validated at the start of the CLI
-                let regex = regex_lite::Regex::new(&include_operation_ids).unwrap();
-                final_outputs = filter_include_operation_ids_outputs(regex, final_outputs);
-            }
+                let spec_output_directory = match &spec_plan.namespace {
+                    Some(namespace) => {
+                        let directory = output_directory.join(namespace);
+                        std::fs::create_dir_all(&directory)?;
+                        directory
+                    }
+                    None => output_directory.clone(),
+                };
+
+                if spec_plan.only_new {
+                    let existing_files: Vec<PathBuf> = std::fs::read_dir(&spec_output_directory)?
+                        .filter_map(|entry| {
+                            if entry.is_err() {
+                                return None;
+                            }
+                            if entry.as_ref().unwrap().file_type().is_err() {
+                                return None;
+                            }
+                            if !entry.as_ref().unwrap().file_type().unwrap().is_file() {
+                                return None;
+                            }
+                            Some(entry.unwrap().path())
+                        })
+                        .collect();
+                    final_outputs = filter_only_new_outputs(&existing_files, final_outputs);
+                }
 
-            if args.only_new {
-                let existing_files: Vec<PathBuf> = std::fs::read_dir(&output_directory)?
-                    .filter_map(|entry| {
-                        if entry.is_err() {
-                            return None;
-                        }
-                        if entry.as_ref().unwrap().file_type().is_err() {
-                            return None;
-                        }
-                        if !entry.as_ref().unwrap().file_type().unwrap().is_file() {
-                            return None;
-                        }
-                        Some(entry.unwrap().path())
-                    })
-                    .collect();
-                final_outputs = filter_only_new_outputs(&existing_files, final_outputs);
-            }
+                write_outputs(&final_outputs, &template, &spec_output_directory)?;
+
+                let mut variables_files = vec![(
+                    variables::VARIABLES_FILE.to_string(),
+                    variables::collect(&spec, &final_outputs),
+                )];
+                variables_files.extend(variables::environments(&spec, &final_outputs));
+                for (file, variables) in variables_files {
+                    let variables_file = spec_output_directory.join(&file);
+                    if !(spec_plan.only_new && variables_file.exists()) {
+                        std::fs::write(variables_file, variables::render(&file, &variables))?;
+                    }
+                }
 
-            write_outputs(&final_outputs, &template, &output_directory)?;
+                has_diagnostics |= !result.diagnostics.is_empty();
+                if plan.show_diagnostics && !result.diagnostics.is_empty() {
+                    if spec_plan.namespace.is_some() {
+                        println!("\n=== Diagnostics for {} ===", input);
+                    }
+                    result.diagnostics.iter().for_each(|d| println!("{}", d));
+                }
+            }
 
-            if args.show_diagnostics {
-                result.diagnostics.iter().for_each(|d| println!("{}", d));
-            } else if !result.diagnostics.is_empty() {
+            if has_diagnostics && !plan.show_diagnostics {
                 eprintln!("Diagnostics are available. Re-run your previous command with `--show-diagnostics` to see them.")
             }
+            if has_parse_failures {
+                return Err("Input spec could not be parsed".into());
+            }
 
             Ok(())
         }
@@ -554,6 +910,77 @@ fn main() -> Result<(), Box<dyn Error>> {
             println!("{}", DEFAULT_HURL_TEMPLATE);
             Ok(())
         }
+        Commands::Bundle(args) => {
+            let content = args.path.read()?;
+            let document = parse::parse_content::<serde_json::Value>(
+                &content,
+                &InputSpecFormat::sniff(&content),
+            )
+            .map_err(|e| e.into_error(&args.path.to_string()));
+            if let Err(e) = document {
+                println!("{}", e);
+                return Err("Input spec could not be parsed".into());
+            }
+            let document = document.unwrap();
+
+            let (bundled, diagnostics) = bundle::bundle(document, &args.path);
+            if !diagnostics.is_empty() {
+                diagnostics.iter().for_each(|d| println!("{}", d));
+                return Err("Some references could not be bundled".into());
+            }
+
+            let is_json = args
+                .output
+                .as_ref()
+                .and_then(|o| o.extension())
+                .is_some_and(|ext| ext == "json");
+            let serialized = if is_json {
+                serde_json::to_string_pretty(&bundled)?
+            } else {
+                serde_yaml::to_string(&bundled)?
+            };
+            match &args.output {
+                Some(output) => std::fs::write(output, serialized)?,
+                None => print!("{}", serialized),
+            }
+            Ok(())
+        }
+        Commands::Init(args) => {
+            let spec_location = match &args.path {
+                InputSource::Stdin => {
+                    return Err(
+                        "The spec must be a path or URL so heave.toml can refer to it".into(),
+                    )
+                }
+                InputSource::File(path) if args.directory != Path::new(".") => {
+                    // The config refers to the spec relative to itself
+                    std::fs::canonicalize(path)?.display().to_string()
+                }
+                other => other.to_string(),
+            };
+            let content = args.path.read()?;
+            let spec = Spec::load(&content, &args.path, &[]);
+            if let Err(e) = spec {
+                println!("{}", e);
+                return Err("Input spec could not be parsed".into());
+            }
+            let spec = spec.unwrap();
+
+            for (file, content) in init::scaffold(&spec_location, spec) {
+                let file = args.directory.join(file);
+                if file.exists() {
+                    println!("Skipped {}, it already exists", file.display());
+                    continue;
+                }
+                if let Some(parent) = file.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&file, content)?;
+                println!("Created {}", file.display());
+            }
+            std::fs::create_dir_all(args.directory.join(init::OUTPUT_DIRECTORY))?;
+            Ok(())
+        }
     }
 }
 
@@ -601,6 +1028,31 @@ fn filter_only_new_outputs(existing_files: &[PathBuf], outputs: Vec<Output>) ->
         .collect()
 }
 
+/// Replaces the hurl variables in the path with the example values of the path parameters.
+fn substitute_path_examples(outputs: Vec<Output>) -> Vec<Output> {
+    outputs
+        .into_iter()
+        .map(|mut output| {
+            for parameter in output.path_parameters.iter() {
+                if let Some(example) = &parameter.example {
+                    output.hurl_path = output.hurl_path.replace(
+                        &format!("{{{{{}}}}}", parameter.name),
+                        &encode_path_segment(example),
+                    );
+                }
+            }
+            output
+        })
+        .collect()
+}
+
+/// Percent-encodes a value so that it stays a single segment of the path, e.g. `/` as `%2F`.
+fn encode_path_segment(value: &str) -> String {
+    let mut url = url::Url::parse("http://localhost/").unwrap();
+    url.path_segments_mut().unwrap().clear().push(value);
+    url.path().trim_start_matches('/').to_string()
+}
+
 fn write_outputs(
     outputs: &[Output],
     template: &str,
@@ -621,11 +1073,17 @@ fn write_outputs(
                 name => output.name,
                 method => output.method,
                 path => output.hurl_path,
+                path_parameters => output.path_parameters,
+                base_url_variable => output.base_url_variable,
                 expected_status_code => output.expected_status_code,
                 header_parameters => output.header_parameters,
                 query_parameters => output.query_parameters,
+                cookie_parameters => output.cookie_parameters,
+                authentication => output.authentication,
                 asserts => output.asserts,
                 request_body_parameter => output.request_body_parameter,
+                request_body_variant => output.request_body_variant,
+                response_body_variant => output.response_body_variant,
             },
             file,
         )?;
@@ -633,129 +1091,68 @@ fn write_outputs(
     Ok(())
 }
 
-fn generate(openapi: openapiv3::OpenAPI) -> GenerateResult {
+fn generate(spec: &mut Spec) -> GenerateResult {
     let mut outputs: Vec<Output> = vec![];
-    let mut diagnostics: Vec<HeaveError> = vec![];
-    for (path, method, operation) in openapi.operations() {
+    let mut diagnostics: Vec<HeaveError> = std::mem::take(&mut spec.diagnostics);
+    for (path, method, operation) in spec.openapi.operations() {
         let name = operation
             .operation_id
             .clone()
             .unwrap_or_else(|| format!("{}_{}", method, path.replace("/", "_")));
         let mut query_parameters: Vec<String> = vec![];
         let mut header_parameters: Vec<String> = vec![];
-        let mut request_body_parameter: Option<String> = None;
+        let mut cookie_parameters: Vec<String> = vec![];
+        let mut path_parameters: Vec<PathParameter> = vec![];
         let context = DiagnosticContext {
             path: path.to_string(),
             operation: name.to_string(),
         };
-        for parameter in operation.parameters.iter() {
-            match parameter {
-                openapiv3::ReferenceOr::Reference { reference } => {
-                    let parameter_name = reference.split("#/components/parameters/").nth(1);
-                    if parameter_name.is_none() {
-                        diagnostics.push(HeaveError::MalformedParameterReference {
-                            operation: name.to_string(),
-                            path: path.to_string(),
-                            reference: reference.to_string(),
-                        });
-                        continue;
-                    }
-                    let parameter_name = parameter_name.unwrap();
-                    let components = &openapi.components;
-                    if components.is_none() {
-                        diagnostics.push(HeaveError::MissingComponents);
-                        continue;
-                    }
-                    let found_parameter =
-                        components.as_ref().unwrap().parameters.get(parameter_name);
-                    if found_parameter.is_none() {
-                        diagnostics.push(HeaveError::MissingParameterReference {
+        let path_item = spec.openapi.paths.paths.get(path).and_then(|p| p.as_item());
+        let base_url_variable = variables::base_url_variable(path, path_item, operation, &name);
+        let (parameters, mut inner_diagnostics) =
+            resolve_parameters(spec, path_item, operation, &context);
+        diagnostics.append(&mut inner_diagnostics);
+        for parameter in parameters.iter() {
+            match parameter.as_ref() {
+                openapiv3::Parameter::Query { parameter_data, .. } => {
+                    query_parameters.push(parameter_data.name.to_string());
+                }
+                openapiv3::Parameter::Header { parameter_data, .. } => {
+                    header_parameters.push(parameter_data.name.to_string());
+                }
+                openapiv3::Parameter::Cookie { parameter_data, .. } => {
+                    cookie_parameters.push(parameter_data.name.to_string());
+                }
+                openapiv3::Parameter::Path { parameter_data, .. } => {
+                    // Path parameters are sent as part of the templated path
+                    if !path.contains(&format!("{{{}}}", parameter_data.name)) {
+                        diagnostics.push(HeaveError::SkippedParameter {
                             context: context.clone(),
-                            reference: reference.to_string(),
+                            parameter: parameter_data.name.to_string(),
+                            location: "path".to_string(),
+                            reason:
+                                "The parameter doesn't appear in the path, so it can't be sent."
+                                    .to_string(),
                         });
                         continue;
                     }
-                    let found_parameter = found_parameter.unwrap();
-                    // TODO add support for reference parameters
-                    if found_parameter.as_item().is_none() {
-                        continue;
-                    }
-                    let found_parameter = found_parameter.as_item().unwrap();
-                    match found_parameter {
-                        openapiv3::Parameter::Query { parameter_data, .. } => {
-                            query_parameters.push(parameter_data.name.to_string());
-                        }
-                        openapiv3::Parameter::Header { parameter_data, .. } => {
-                            header_parameters.push(parameter_data.name.to_string());
-                        }
-                        _ => {}
-                    }
+                    let (path_parameter, mut inner_diagnostics) =
+                        generate_path_parameter(spec, parameter_data, &context);
+                    diagnostics.append(&mut inner_diagnostics);
+                    path_parameters.push(path_parameter);
                 }
-                openapiv3::ReferenceOr::Item(item) => match item {
-                    openapiv3::Parameter::Query { parameter_data, .. } => {
-                        query_parameters.push(parameter_data.name.to_string());
-                    }
-                    openapiv3::Parameter::Header { parameter_data, .. } => {
-                        header_parameters.push(parameter_data.name.to_string());
-                    }
-                    _ => {}
-                },
             }
         }
 
-        while let Some(request_body) = &operation.request_body {
-            let (request_body, mut inner_diagnostics) =
-                resolve_request_body(&openapi, request_body, &context);
-            diagnostics.append(&mut inner_diagnostics);
-            if request_body.is_none() {
-                break;
-            }
-            let request_body = request_body.unwrap();
-            let mut media_type: Option<&MediaType> = None;
-            for (media_type_key, media_type_val) in request_body.content.iter() {
-                if media_type_key.starts_with("application/json") {
-                    media_type = Some(media_type_val);
-                    break;
-                }
-            }
-            if media_type.is_none() {
-                diagnostics.push(HeaveError::MissingApplicationJsonRequestBodyMediaType {
-                    context: context.clone(),
-                });
-                break;
-            }
-            let media_type = media_type.unwrap();
-            let schema = &media_type.schema;
-            if schema.is_none() {
-                diagnostics.push(HeaveError::MissingSchemaDefinitionForMediaType {
-                    context: context.clone(),
-                });
-                break;
-            }
-            let schema = schema.as_ref().unwrap();
-            let (schema, mut inner_diagnostics) = resolve_schema(&openapi, schema, &context);
-            diagnostics.append(&mut inner_diagnostics);
-            if schema.is_none() {
-                break;
-            }
-            let schema = schema.unwrap();
-            let request_body_parameter_tuple =
-                generate_request_body_from_schema(&openapi, schema, None, &context, "$");
-            request_body_parameter = request_body_parameter_tuple.0;
-            let mut inner_diagnostics = request_body_parameter_tuple.1;
-            diagnostics.append(&mut inner_diagnostics);
-            if let Some(body) = &request_body_parameter {
-                let a = serde_json::from_str::<serde_json::Value>(body).unwrap();
-                let body = serde_json::to_string_pretty(&a);
-                if let Ok(body) = body {
-                    request_body_parameter = Some(body);
-                }
-            };
-            break;
-        }
+        let (authentication, mut inner_diagnostics) =
+            security::authentication(spec, operation, &base_url_variable, &context);
+        diagnostics.append(&mut inner_diagnostics);
+
+        let (request_body_variants, mut inner_diagnostics) =
+            generate_request_body_variants(spec, operation, &context);
+        diagnostics.append(&mut inner_diagnostics);
 
         for (status_code, response) in operation.responses.responses.iter() {
-            let mut asserts: Vec<String> = vec![];
             match status_code {
                 openapiv3::StatusCode::Range(_) => {
                     diagnostics.push(HeaveError::UnsupportedStatusCodeRange {
@@ -763,9 +1160,8 @@ fn generate(openapi: openapiv3::OpenAPI) -> GenerateResult {
                     })
                 }
                 openapiv3::StatusCode::Code(code) => {
-                    let name = format!("{}_{}.hurl", name, code);
                     let (response, mut inner_diagnostics) =
-                        resolve_response(&openapi, response, &context);
+                        resolve_reference(spec, response, &context);
                     diagnostics.append(&mut inner_diagnostics);
                     if response.is_none() {
                         continue;
@@ -778,64 +1174,85 @@ fn generate(openapi: openapiv3::OpenAPI) -> GenerateResult {
                             break;
                         }
                     }
-                    if media_type.is_none() {
-                        let output = Output {
-                            expected_status_code: *code,
-                            name,
-                            hurl_path: path.to_string().replace("{", "{{").replace("}", "}}"),
-                            oas_path: path.to_string(),
-                            oas_operation_id: operation.operation_id.clone(),
-                            method: method.to_string().to_uppercase(),
-                            header_parameters: header_parameters.clone(),
-                            query_parameters: query_parameters.clone(),
-                            asserts: vec![],
-                            request_body_parameter: request_body_parameter
-                                .clone()
-                                .unwrap_or("".to_string()),
-                        };
-                        outputs.push(output);
-                        continue;
-                    }
-                    let schema = media_type.unwrap().schema.as_ref();
-                    if schema.is_none() {
-                        diagnostics.push(HeaveError::MissingSchemaDefinitionForMediaType {
-                            context: context.clone(),
-                        });
-                        continue;
+                    // Responses without an application/json media type still get a file, they
+                    // just don't have any asserts.
+                    let mut response_body_variants = vec![ResponseBodyVariant {
+                        name: None,
+                        asserts: vec![],
+                    }];
+                    if let Some(media_type) = media_type {
+                        let schema = media_type.schema.as_ref();
+                        if schema.is_none() {
+                            diagnostics.push(HeaveError::MissingSchemaDefinitionForMediaType {
+                                context: context.clone(),
+                            });
+                            continue;
+                        }
+                        let schema = schema.unwrap();
+                        let (schema, mut inner_diagnostics) =
+                            resolve_reference(spec, schema, &context);
+                        diagnostics.append(&mut inner_diagnostics);
+                        if schema.is_none() {
+                            continue;
+                        }
+                        let schema = schema.unwrap();
+                        let mut inner_diagnostics;
+                        (response_body_variants, inner_diagnostics) =
+                            generate_response_body_variants(spec, &schema, &context);
+                        diagnostics.append(&mut inner_diagnostics);
                     }
-                    let schema = schema.unwrap();
-                    let (schema, mut inner_diagnostics) =
-                        resolve_schema(&openapi, schema, &context);
-                    diagnostics.append(&mut inner_diagnostics);
-                    if schema.is_none() {
-                        continue;
+
+                    // Each request body variant gets its own file. Operations without a oneOf
+                    // request body only have a single, unnamed variant.
+                    for request_body_variant in request_body_variants.iter() {
+                        // When a request body variant has a matching response variant (e.g. a
+                        // `Card` is sent and a `Card` is returned) only that pairing is
+                        // generated. Otherwise every response variant is paired with it.
+                        let matching_response_variants: Vec<_> = response_body_variants
+                            .iter()
+                            .filter(|v| v.name.is_some() && v.name == request_body_variant.name)
+                            .collect();
+                        let paired_response_variants = if matching_response_variants.is_empty() {
+                            response_body_variants.iter().collect()
+                        } else {
+                            matching_response_variants
+                        };
+                        for response_body_variant in paired_response_variants {
+                            let variant_names: Vec<_> =
+                                [&request_body_variant.name, &response_body_variant.name]
+                                    .into_iter()
+                                    .flatten()
+                                    .unique()
+                                    .collect();
+                            let name = if variant_names.is_empty() {
+                                format!("{}_{}.hurl", name, code)
+                            } else {
+                                format!("{}_{}_{}.hurl", name, variant_names.iter().join("_"), code)
+                            };
+                            let output = Output {
+                                expected_status_code: *code,
+                                name,
+                                hurl_path: path.to_string().replace("{", "{{").replace("}", "}}"),
+                                oas_path: path.to_string(),
+                                oas_operation_id: operation.operation_id.clone(),
+                                method: method.to_string().to_uppercase(),
+                                path_parameters: path_parameters.clone(),
+                                base_url_variable: base_url_variable.clone(),
+                                header_parameters: header_parameters.clone(),
+                                query_parameters: query_parameters.clone(),
+                                cookie_parameters: cookie_parameters.clone(),
+                                authentication: authentication.clone(),
+                                asserts: response_body_variant.asserts.clone(),
+                                request_body_parameter: request_body_variant
+                                    .body
+                                    .clone()
+                                    .unwrap_or_default(),
+                                request_body_variant: request_body_variant.name.clone(),
+                                response_body_variant: response_body_variant.name.clone(),
+                            };
+                            outputs.push(output)
+                        }
                     }
-                    let schema = schema.unwrap();
-                    let is_required = true;
-                    let (mut new_asserts, mut new_diagnostics) =
-                        generate_assert_from_schema(&openapi, schema, "$", is_required, &context);
-                    asserts.append(&mut new_asserts);
-                    diagnostics.append(&mut new_diagnostics);
-
-                    // It's possible for identical asserts to be generated when dealing with
-                    // polymorphic attributes (like allOf). This cleans that up.
-                    let asserts: Vec<_> = asserts.into_iter().unique().collect();
-
-                    let output = Output {
-                        expected_status_code: *code,
-                        name,
-                        hurl_path: path.to_string().replace("{", "{{").replace("}", "}}"),
-                        oas_path: path.to_string(),
-                        oas_operation_id: operation.operation_id.clone(),
-                        method: method.to_string().to_uppercase(),
-                        header_parameters: header_parameters.clone(),
-                        query_parameters: query_parameters.clone(),
-                        asserts: asserts.clone(),
-                        request_body_parameter: request_body_parameter
-                            .clone()
-                            .unwrap_or("".to_string()),
-                    };
-                    outputs.push(output)
                 }
             };
         }
@@ -847,8 +1264,296 @@ fn generate(openapi: openapiv3::OpenAPI) -> GenerateResult {
     }
 }
 
+fn generate_path_parameter(
+    spec: &Spec,
+    parameter_data: &openapiv3::ParameterData,
+    context: &DiagnosticContext,
+) -> (PathParameter, Vec<HeaveError>) {
+    let (example, diagnostics) = variables::example_value(spec, parameter_data, context);
+    let mut schema_type = None;
+    if let openapiv3::ParameterSchemaOrContent::Schema(schema) = &parameter_data.format {
+        // Problems with the schema were reported when looking for the example
+        let (schema, _) = resolve_reference(spec, schema, context);
+        schema_type = match schema.as_ref().map(|s| &s.schema_kind) {
+            Some(openapiv3::SchemaKind::Type(schema_type)) => Some(match schema_type {
+                openapiv3::Type::String(_) => "string",
+                openapiv3::Type::Number(_) => "number",
+                openapiv3::Type::Integer(_) => "integer",
+                openapiv3::Type::Object(_) => "object",
+                openapiv3::Type::Array(_) => "array",
+                openapiv3::Type::Boolean(_) => "boolean",
+            }),
+            _ => None,
+        };
+    }
+    let path_parameter = PathParameter {
+        name: parameter_data.name.clone(),
+        schema_type: schema_type.map(|t| t.to_string()),
+        required: parameter_data.required,
+        description: parameter_data.description.clone(),
+        example,
+    };
+    (path_parameter, diagnostics)
+}
+
+fn generate_request_body_variants(
+    spec: &Spec,
+    operation: &openapiv3::Operation,
+    diagnostic_context: &DiagnosticContext,
+) -> (Vec<RequestBodyVariant>, Vec<HeaveError>) {
+    let mut diagnostics: Vec<HeaveError> = vec![];
+    let empty_variant = || {
+        vec![RequestBodyVariant {
+            name: None,
+            body: None,
+        }]
+    };
+    let request_body = match &operation.request_body {
+        Some(request_body) => request_body,
+        None => return (empty_variant(), diagnostics),
+    };
+    let (request_body, mut inner_diagnostics) =
+        resolve_reference(spec, request_body, diagnostic_context);
+    diagnostics.append(&mut inner_diagnostics);
+    if request_body.is_none() {
+        return (empty_variant(), diagnostics);
+    }
+    let request_body = request_body.unwrap();
+    let mut media_type: Option<&MediaType> = None;
+    for (media_type_key, media_type_val) in request_body.content.iter() {
+        if media_type_key.starts_with("application/json") {
+            media_type = Some(media_type_val);
+            break;
+        }
+    }
+    if media_type.is_none() {
+        diagnostics.push(HeaveError::MissingApplicationJsonRequestBodyMediaType {
+            context: diagnostic_context.clone(),
+        });
+        return (empty_variant(), diagnostics);
+    }
+    let media_type = media_type.unwrap();
+    let schema = &media_type.schema;
+    if schema.is_none() {
+        diagnostics.push(HeaveError::MissingSchemaDefinitionForMediaType {
+            context: diagnostic_context.clone(),
+        });
+        return (empty_variant(), diagnostics);
+    }
+    let schema = schema.as_ref().unwrap();
+    let (schema, mut inner_diagnostics) = resolve_reference(spec, schema, diagnostic_context);
+    diagnostics.append(&mut inner_diagnostics);
+    if schema.is_none() {
+        return (empty_variant(), diagnostics);
+    }
+    let schema = schema.unwrap();
+
+    let discriminator = schema.schema_data.discriminator.as_ref();
+    // Without a discriminator there is no telling which branches an anyOf body combines, so it
+    // stays a single body like any other schema.
+    let branches = match (&schema.schema_kind, discriminator) {
+        (openapiv3::SchemaKind::OneOf { one_of }, _) => one_of,
+        (openapiv3::SchemaKind::AnyOf { any_of }, Some(_)) => any_of,
+        _ => {
+            let (body, mut inner_diagnostics) =
+                generate_request_body_from_schema(spec, &schema, None, diagnostic_context, "$");
+            diagnostics.append(&mut inner_diagnostics);
+            let variant = RequestBodyVariant {
+                name: None,
+                body: body.map(prettify_request_body),
+            };
+            return (vec![variant], diagnostics);
+        }
+    };
+
+    let (schema_variants, mut inner_diagnostics) =
+        resolve_schema_variants(spec, branches, discriminator, diagnostic_context);
+    diagnostics.append(&mut inner_diagnostics);
+    let mut variants = vec![];
+    for schema_variant in schema_variants {
+        let (body, mut inner_diagnostics) = generate_request_body_from_schema(
+            spec,
+            &schema_variant.schema,
+            None,
+            diagnostic_context,
+            "$",
+        );
+        diagnostics.append(&mut inner_diagnostics);
+        let body = match (body, discriminator, &schema_variant.discriminator_value) {
+            (Some(body), Some(discriminator), Some(value)) => Some(apply_discriminator_value(
+                body,
+                &discriminator.property_name,
+                value,
+            )),
+            (body, _, _) => body,
+        };
+        variants.push(RequestBodyVariant {
+            name: Some(schema_variant.name),
+            body: body.map(prettify_request_body),
+        });
+    }
+    if variants.is_empty() {
+        return (empty_variant(), diagnostics);
+    }
+    (variants, diagnostics)
+}
+
+fn generate_response_body_variants(
+    spec: &Spec,
+    schema: &openapiv3::Schema,
+    diagnostic_context: &DiagnosticContext,
+) -> (Vec<ResponseBodyVariant>, Vec<HeaveError>) {
+    let mut diagnostics: Vec<HeaveError> = vec![];
+    let is_required = true;
+    let branches = match &schema.schema_kind {
+        openapiv3::SchemaKind::OneOf { one_of } => Some(one_of),
+        openapiv3::SchemaKind::AnyOf { any_of } => Some(any_of),
+        _ => None,
+    };
+
+    // Responses can only be split into variants when a discriminator tells us which branch is
+    // expected. Without one there is no way to know which asserts apply.
+    if let (Some(branches), Some(discriminator)) =
+        (branches, schema.schema_data.discriminator.as_ref())
+    {
+        let (schema_variants, mut inner_diagnostics) =
+            resolve_schema_variants(spec, branches, Some(discriminator), diagnostic_context);
+        diagnostics.append(&mut inner_diagnostics);
+        let mut variants = vec![];
+        for schema_variant in schema_variants {
+            let mut asserts = vec![];
+            if let Some(value) = &schema_variant.discriminator_value {
+                asserts.push(discriminator_assert(
+                    "$",
+                    &discriminator.property_name,
+                    value,
+                    is_required,
+                ));
+            }
+            let (mut new_asserts, mut new_diagnostics) = generate_assert_from_schema(
+                spec,
+                &schema_variant.schema,
+                "$",
+                is_required,
+                diagnostic_context,
+            );
+            asserts.append(&mut new_asserts);
+            diagnostics.append(&mut new_diagnostics);
+            variants.push(ResponseBodyVariant {
+                name: Some(schema_variant.name),
+                // It's possible for identical asserts to be generated when dealing with
+                // polymorphic attributes (like allOf). This cleans that up.
+                asserts: asserts.into_iter().unique().collect(),
+            });
+        }
+        if !variants.is_empty() {
+            return (variants, diagnostics);
+        }
+    }
+
+    let (asserts, mut new_diagnostics) =
+        generate_assert_from_schema(spec, schema, "$", is_required, diagnostic_context);
+    diagnostics.append(&mut new_diagnostics);
+    let variant = ResponseBodyVariant {
+        name: None,
+        asserts: asserts.into_iter().unique().collect(),
+    };
+    (vec![variant], diagnostics)
+}
+
+/// Resolves every branch of a oneOf or anyOf schema.
+///
+/// Branches are named after the component they reference. Inline schemas don't have a name so
+/// their position in the list is used instead. When a discriminator is present, each referenced
+/// branch is given the value from the discriminator's mapping, falling back to the component
+/// name as described by the OpenAPI spec.
+fn resolve_schema_variants<'a>(
+    spec: &'a Spec,
+    branches: &'a [ReferenceOr<openapiv3::Schema>],
+    discriminator: Option<&openapiv3::Discriminator>,
+    diagnostic_context: &DiagnosticContext,
+) -> (Vec<SchemaVariant<'a>>, Vec<HeaveError>) {
+    let mut diagnostics: Vec<HeaveError> = vec![];
+    let mut variants = vec![];
+    for (index, schema_or_ref) in branches.iter().enumerate() {
+        let (name, discriminator_value) = match schema_or_ref {
+            ReferenceOr::Reference { reference } => {
+                let component_name = reference.rsplit('/').next().unwrap_or_default();
+                let discriminator_value = discriminator.map(|discriminator| {
+                    discriminator
+                        .mapping
+                        .iter()
+                        .find(|(_, target)| *target == reference || *target == component_name)
+                        .map(|(value, _)| value.to_string())
+                        .unwrap_or(component_name.to_string())
+                });
+                (component_name.to_string(), discriminator_value)
+            }
+            ReferenceOr::Item(_) => (index.to_string(), None),
+        };
+        let (schema, mut inner_diagnostics) =
+            resolve_reference(spec, schema_or_ref, diagnostic_context);
+        diagnostics.append(&mut inner_diagnostics);
+        if let Some(schema) = schema {
+            variants.push(SchemaVariant {
+                name,
+                schema,
+                discriminator_value,
+            });
+        }
+    }
+    (variants, diagnostics)
+}
+
+/// Sets the discriminator property on a generated request body.
+fn apply_discriminator_value(body: String, property_name: &str, value: &str) -> String {
+    if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&body) {
+        if let Some(object) = json.as_object_mut() {
+            object.insert(
+                property_name.to_string(),
+                serde_json::Value::String(value.to_string()),
+            );
+            return json.to_string();
+        }
+    }
+    body
+}
+
+fn discriminator_assert(
+    jsonpath: &str,
+    property_name: &str,
+    value: &str,
+    is_required: bool,
+) -> String {
+    format!(
+        "{}jsonpath \"{}\" == {}",
+        if is_required { "" } else { "#" },
+        child_jsonpath(jsonpath, property_name),
+        serde_json::Value::String(value.to_string())
+    )
+}
+
+/// There are characters that aren't allowed in jsonpath so we change the format if they're
+/// present.
+fn child_jsonpath(jsonpath: &str, name: &str) -> String {
+    if name.chars().any(|c| c == '@' || c == '$') {
+        format!("{}['{}']", jsonpath, name)
+    } else {
+        format!("{}.{}", jsonpath, name)
+    }
+}
+
+/// Request bodies are built up as strings, so they are reformatted at the end to make sure the
+/// output is consistently indented.
+fn prettify_request_body(body: String) -> String {
+    match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(value) => serde_json::to_string_pretty(&value).unwrap_or(body),
+        Err(_) => body,
+    }
+}
+
 fn generate_assert_from_schema(
-    openapi: &openapiv3::OpenAPI,
+    spec: &Spec,
     schema: &openapiv3::Schema,
     jsonpath: &str,
     is_required: bool,
@@ -868,7 +1573,7 @@ fn generate_assert_from_schema(
         }
         // Check if the immediate next part is the same as the current part. If it is, we have a
         // cycle
-        if parts.peek().map_or(false, |next| *next == part) {
+        if parts.peek().is_some_and(|next| *next == part) {
             return (
                 vec![],
                 vec![HeaveError::ResponseBodySchemaCycleDetected {
@@ -880,7 +1585,7 @@ fn generate_assert_from_schema(
         // Check the next part
         let mut peek_again = parts.clone();
         let _ = peek_again.next();
-        if peek_again.next().map_or(false, |next| next == part) {
+        if peek_again.next().is_some_and(|next| next == part) {
             return (
                 vec![],
                 vec![HeaveError::ResponseBodySchemaCycleDetected {
@@ -901,7 +1606,46 @@ fn generate_assert_from_schema(
             default
         )
     };
+    // A nullable value may be `null` instead of matching its schema, so the only thing that can
+    // be required is that the value is there.
+    let mut is_required = is_required;
+    if schema.schema_data.nullable && is_required {
+        asserts.push(is_required_formatter(jsonpath, "exists", is_required));
+        is_required = false;
+    }
     match &schema.schema_kind {
+        openapiv3::SchemaKind::OneOf { one_of: branches }
+        | openapiv3::SchemaKind::AnyOf { any_of: branches }
+            if schema.schema_data.discriminator.is_some() =>
+        {
+            // Top level discriminated responses are split into variants before reaching this
+            // point. Nested ones can't be, so the asserts for every branch are generated but
+            // commented out, each preceded by the discriminator value that selects it.
+            let discriminator = schema.schema_data.discriminator.as_ref().unwrap();
+            let (schema_variants, mut inner_diagnostics) =
+                resolve_schema_variants(spec, branches, Some(discriminator), diagnostic_context);
+            diagnostics.append(&mut inner_diagnostics);
+            let is_required = false;
+            for schema_variant in schema_variants {
+                if let Some(value) = &schema_variant.discriminator_value {
+                    asserts.push(discriminator_assert(
+                        jsonpath,
+                        &discriminator.property_name,
+                        value,
+                        is_required,
+                    ));
+                }
+                let (mut child_asserts, mut child_diagnostics) = generate_assert_from_schema(
+                    spec,
+                    &schema_variant.schema,
+                    jsonpath,
+                    is_required,
+                    diagnostic_context,
+                );
+                asserts.append(&mut child_asserts);
+                diagnostics.append(&mut child_diagnostics);
+            }
+        }
         openapiv3::SchemaKind::OneOf { .. } => {
             diagnostics.push(HeaveError::UnsupportedSchemaKind {
                 context: diagnostic_context.clone(),
@@ -912,13 +1656,13 @@ fn generate_assert_from_schema(
         openapiv3::SchemaKind::AllOf { all_of } => {
             for all_of_schema_or_ref in all_of {
                 let (all_of_schema, mut inner_diagnostics) =
-                    resolve_schema(openapi, all_of_schema_or_ref, diagnostic_context);
+                    resolve_reference(spec, all_of_schema_or_ref, diagnostic_context);
                 diagnostics.append(&mut inner_diagnostics);
 
                 if let Some(s) = all_of_schema {
                     let (mut child_asserts, mut child_diagnostics) = generate_assert_from_schema(
-                        openapi,
-                        s,
+                        spec,
+                        &s,
                         jsonpath,
                         is_required,
                         diagnostic_context,
@@ -928,23 +1672,78 @@ fn generate_assert_from_schema(
                 }
             }
         }
-        openapiv3::SchemaKind::AnyOf { .. } => {
-            diagnostics.push(HeaveError::UnsupportedSchemaKind {
-                context: diagnostic_context.clone(),
-                kind: "AnyOf".to_string(),
-                jsonpath: jsonpath.to_string(),
-            })
+        openapiv3::SchemaKind::AnyOf { any_of } => {
+            // Asserts that hold for every branch can be used as is. The rest only apply to some
+            // of the branches, so they are commented out and labelled with the branch they came
+            // from. The leading `#` is ignored when comparing so that a property that is only
+            // optional in some branches is still considered common.
+            let (schema_variants, mut inner_diagnostics) =
+                resolve_schema_variants(spec, any_of, None, diagnostic_context);
+            diagnostics.append(&mut inner_diagnostics);
+            let mut branch_asserts: Vec<(String, Vec<String>)> = vec![];
+            for schema_variant in schema_variants {
+                let (child_asserts, mut child_diagnostics) = generate_assert_from_schema(
+                    spec,
+                    &schema_variant.schema,
+                    jsonpath,
+                    is_required,
+                    diagnostic_context,
+                );
+                diagnostics.append(&mut child_diagnostics);
+                branch_asserts.push((schema_variant.name, child_asserts));
+            }
+            let uncommented = |assert: &str| assert.trim_start_matches('#').to_string();
+            let is_common = |assert: &str| {
+                branch_asserts.iter().all(|(_, child_asserts)| {
+                    child_asserts
+                        .iter()
+                        .any(|child_assert| uncommented(child_assert) == uncommented(assert))
+                })
+            };
+            let is_required_in_all_branches = |assert: &str| {
+                branch_asserts.iter().all(|(_, child_asserts)| {
+                    child_asserts
+                        .iter()
+                        .any(|child_assert| child_assert == assert)
+                })
+            };
+            if let Some((_, first_branch_asserts)) = branch_asserts.first() {
+                for assert in first_branch_asserts.iter().filter(|a| is_common(a)) {
+                    let assert = uncommented(assert);
+                    if is_required_in_all_branches(&assert) {
+                        asserts.push(assert);
+                    } else {
+                        asserts.push(format!("#{}", assert));
+                    }
+                }
+            }
+            for (branch_name, child_asserts) in branch_asserts.iter() {
+                for assert in child_asserts.iter().filter(|a| !is_common(a)) {
+                    asserts.push(format!("#{} # anyOf: {}", uncommented(assert), branch_name));
+                }
+            }
         }
-        openapiv3::SchemaKind::Not { .. } => diagnostics.push(HeaveError::UnsupportedSchemaKind {
-            context: diagnostic_context.clone(),
-            kind: "Not".to_string(),
-            jsonpath: jsonpath.to_string(),
-        }),
-        openapiv3::SchemaKind::Any(_) => diagnostics.push(HeaveError::UnsupportedSchemaKind {
-            context: diagnostic_context.clone(),
-            kind: "Any".to_string(),
-            jsonpath: jsonpath.to_string(),
-        }),
+        openapiv3::SchemaKind::Not { .. } => {
+            // There is no way to assert that a value doesn't match a schema, so the best we can
+            // do is check that the value is there.
+            asserts.push(format!("# {} must not match its `not` schema", jsonpath));
+            asserts.push(is_required_formatter(jsonpath, "exists", is_required));
+        }
+        openapiv3::SchemaKind::Any(any) => match infer_schema_from_any(&schema.schema_data, any) {
+            Some(inferred) => {
+                let (mut child_asserts, mut child_diagnostics) = generate_assert_from_schema(
+                    spec,
+                    &inferred,
+                    jsonpath,
+                    is_required,
+                    diagnostic_context,
+                );
+                asserts.append(&mut child_asserts);
+                diagnostics.append(&mut child_diagnostics);
+            }
+            // A free-form schema allows any value.
+            None => asserts.push(is_required_formatter(jsonpath, "exists", is_required)),
+        },
         openapiv3::SchemaKind::Type(schema_type) => {
             match schema_type {
                 openapiv3::Type::Boolean(_) => {
@@ -968,7 +1767,7 @@ fn generate_assert_from_schema(
                     let items = items.as_ref().unwrap();
                     let unboxed = items.clone().unbox();
                     let (inner, mut inner_diagnostics) =
-                        resolve_schema(openapi, &unboxed, diagnostic_context);
+                        resolve_reference(spec, &unboxed, diagnostic_context);
                     diagnostics.append(&mut inner_diagnostics);
                     if inner.is_none() {
                         return (asserts, diagnostics);
@@ -982,8 +1781,8 @@ fn generate_assert_from_schema(
                     let is_required = false;
 
                     let (mut child_asserts, mut child_diagnostics) = generate_assert_from_schema(
-                        openapi,
-                        inner,
+                        spec,
+                        &inner,
                         inner_jsonpath.as_ref(),
                         is_required,
                         diagnostic_context,
@@ -997,25 +1796,19 @@ fn generate_assert_from_schema(
                     for (name, prop) in properties.iter() {
                         let unboxed = prop.clone().unbox();
                         let (inner, mut inner_diagnostics) =
-                            resolve_schema(openapi, &unboxed, diagnostic_context);
+                            resolve_reference(spec, &unboxed, diagnostic_context);
                         diagnostics.append(&mut inner_diagnostics);
                         if inner.is_none() {
                             break;
                         }
                         let inner = inner.unwrap();
 
-                        // There are characters that aren't allowed in jsonpath so we change the format
-                        // if they're present.
-                        let inner_jsonpath = if name.chars().any(|c| c == '@' || c == '$') {
-                            format!("{}['{}']", jsonpath, name)
-                        } else {
-                            format!("{}.{}", jsonpath, name)
-                        };
+                        let inner_jsonpath = child_jsonpath(jsonpath, name);
                         let child_is_required = is_required && ob.required.contains(name);
                         let (mut child_asserts, mut child_diagnostics) =
                             generate_assert_from_schema(
-                                openapi,
-                                inner,
+                                spec,
+                                &inner,
                                 inner_jsonpath.as_ref(),
                                 child_is_required,
                                 diagnostic_context,
@@ -1025,107 +1818,462 @@ fn generate_assert_from_schema(
                     }
                 }
             }
+            if let Some(value) = const_value(schema_type) {
+                asserts.push(is_required_formatter(
+                    jsonpath,
+                    &format!("== {}", value),
+                    is_required,
+                ));
+            }
         }
     }
     (asserts, diagnostics)
 }
 
-fn resolve_schema<'a>(
-    openapi: &'a openapiv3::OpenAPI,
-    schema: &'a openapiv3::ReferenceOr<openapiv3::Schema>,
+/// Returns the only value a schema allows, which is how `const` is represented (a single value
+/// `enum`).
+fn const_value(schema_type: &openapiv3::Type) -> Option<serde_json::Value> {
+    let value = match schema_type {
+        openapiv3::Type::String(s) => match s.enumeration.as_slice() {
+            [Some(value)] => serde_json::Value::from(value.as_str()),
+            _ => return None,
+        },
+        openapiv3::Type::Number(n) => match n.enumeration.as_slice() {
+            [Some(value)] => serde_json::Value::from(*value),
+            _ => return None,
+        },
+        openapiv3::Type::Integer(i) => match i.enumeration.as_slice() {
+            [Some(value)] => serde_json::Value::from(*value),
+            _ => return None,
+        },
+        openapiv3::Type::Boolean(b) => match b.enumeration.as_slice() {
+            [Some(value)] => serde_json::Value::from(*value),
+            _ => return None,
+        },
+        openapiv3::Type::Object(_) | openapiv3::Type::Array(_) => return None,
+    };
+    Some(value)
+}
+
+/// Schemas without a `type` are parsed as `Any`. This builds the schema that was most likely
+/// intended based on the fields that are present. `None` is returned for free-form schemas that
+/// don't constrain the value at all.
+fn infer_schema_from_any(
+    schema_data: &openapiv3::SchemaData,
+    any: &openapiv3::AnySchema,
+) -> Option<openapiv3::Schema> {
+    let mut parts: Vec<ReferenceOr<openapiv3::Schema>> = vec![];
+    if let Some(schema_type) = infer_type_from_any(any) {
+        parts.push(ReferenceOr::Item(openapiv3::Schema {
+            schema_data: Default::default(),
+            schema_kind: openapiv3::SchemaKind::Type(schema_type),
+        }));
+    }
+    // Composition keywords can be mixed with the other fields (e.g. `type: object` alongside a
+    // `oneOf`), in which case the value has to satisfy all of them.
+    let discriminated_schema_data = openapiv3::SchemaData {
+        discriminator: schema_data.discriminator.clone(),
+        ..Default::default()
+    };
+    if !any.one_of.is_empty() {
+        parts.push(ReferenceOr::Item(openapiv3::Schema {
+            schema_data: discriminated_schema_data.clone(),
+            schema_kind: openapiv3::SchemaKind::OneOf {
+                one_of: any.one_of.clone(),
+            },
+        }));
+    }
+    if !any.any_of.is_empty() {
+        parts.push(ReferenceOr::Item(openapiv3::Schema {
+            schema_data: discriminated_schema_data,
+            schema_kind: openapiv3::SchemaKind::AnyOf {
+                any_of: any.any_of.clone(),
+            },
+        }));
+    }
+    parts.extend(any.all_of.iter().cloned());
+
+    let schema_kind = match (parts.len(), any.all_of.is_empty()) {
+        (0, _) => return None,
+        (1, true) => parts.pop().unwrap().into_item().unwrap().schema_kind,
+        _ => openapiv3::SchemaKind::AllOf { all_of: parts },
+    };
+    Some(openapiv3::Schema {
+        schema_data: schema_data.clone(),
+        schema_kind,
+    })
+}
+
+fn infer_type_from_any(any: &openapiv3::AnySchema) -> Option<openapiv3::Type> {
+    let typ = match &any.typ {
+        Some(typ) => typ.as_str(),
+        None if !any.properties.is_empty()
+            || any.additional_properties.is_some()
+            || any.min_properties.is_some()
+            || any.max_properties.is_some() =>
+        {
+            "object"
+        }
+        None if any.items.is_some()
+            || any.min_items.is_some()
+            || any.max_items.is_some()
+            || any.unique_items.is_some() =>
+        {
+            "array"
+        }
+        None if !any.enumeration.is_empty() => {
+            match any.enumeration.iter().find(|v| !v.is_null())? {
+                serde_json::Value::Bool(_) => "boolean",
+                serde_json::Value::Number(n) if n.is_f64() => "number",
+                serde_json::Value::Number(_) => "integer",
+                serde_json::Value::String(_) => "string",
+                serde_json::Value::Array(_) => "array",
+                serde_json::Value::Object(_) => "object",
+                serde_json::Value::Null => return None,
+            }
+        }
+        None if any.format.is_some() => match any.format.as_deref() {
+            Some("int32") | Some("int64") => "integer",
+            Some("float") | Some("double") => "number",
+            _ => "string",
+        },
+        None if any.pattern.is_some() || any.min_length.is_some() || any.max_length.is_some() => {
+            "string"
+        }
+        None if any.minimum.is_some() || any.maximum.is_some() || any.multiple_of.is_some() => {
+            "number"
+        }
+        None => return None,
+    };
+    let schema_type = match typ {
+        "object" => openapiv3::Type::Object(openapiv3::ObjectType {
+            properties: any.properties.clone(),
+            required: any.required.clone(),
+            additional_properties: any.additional_properties.clone(),
+            min_properties: any.min_properties,
+            max_properties: any.max_properties,
+        }),
+        "array" => openapiv3::Type::Array(openapiv3::ArrayType {
+            items: any.items.clone(),
+            min_items: any.min_items,
+            max_items: any.max_items,
+            unique_items: any.unique_items.unwrap_or_default(),
+        }),
+        "string" => openapiv3::Type::String(openapiv3::StringType {
+            format: any.format.clone().into(),
+            pattern: any.pattern.clone(),
+            enumeration: any
+                .enumeration
+                .iter()
+                .map(|v| v.as_str().map(String::from))
+                .collect(),
+            min_length: any.min_length,
+            max_length: any.max_length,
+        }),
+        "number" => openapiv3::Type::Number(openapiv3::NumberType {
+            format: any.format.clone().into(),
+            minimum: any.minimum,
+            maximum: any.maximum,
+            multiple_of: any.multiple_of,
+            enumeration: any.enumeration.iter().map(|v| v.as_f64()).collect(),
+            ..Default::default()
+        }),
+        "integer" => openapiv3::Type::Integer(openapiv3::IntegerType {
+            format: any.format.clone().into(),
+            enumeration: any.enumeration.iter().map(|v| v.as_i64()).collect(),
+            ..Default::default()
+        }),
+        "boolean" => openapiv3::Type::Boolean(openapiv3::BooleanType {
+            enumeration: any.enumeration.iter().map(|v| v.as_bool()).collect(),
+        }),
+        _ => return None,
+    };
+    Some(schema_type)
+}
+
+/// An object that can be defined in `#/components/` and referenced with a `$ref`.
+trait Component: Clone + serde::de::DeserializeOwned {
+    /// The key under `components` that holds this kind of component.
+    const COMPONENTS_KEY: &'static str;
+
+    fn lookup<'a>(
+        components: &'a openapiv3::Components,
+        name: &str,
+    ) -> Option<&'a ReferenceOr<Self>>;
+
+    fn malformed_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError;
+
+    fn missing_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError;
+}
+
+impl Component for openapiv3::Schema {
+    const COMPONENTS_KEY: &'static str = "schemas";
+
+    fn lookup<'a>(
+        components: &'a openapiv3::Components,
+        name: &str,
+    ) -> Option<&'a ReferenceOr<Self>> {
+        components.schemas.get(name)
+    }
+
+    fn malformed_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MalformedSchemaReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+
+    fn missing_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MissingSchemaReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+}
+
+impl Component for openapiv3::RequestBody {
+    const COMPONENTS_KEY: &'static str = "requestBodies";
+
+    fn lookup<'a>(
+        components: &'a openapiv3::Components,
+        name: &str,
+    ) -> Option<&'a ReferenceOr<Self>> {
+        components.request_bodies.get(name)
+    }
+
+    fn malformed_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MalformedRequestBodyReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+
+    fn missing_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MissingRequestBodyReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+}
+
+impl Component for openapiv3::Response {
+    const COMPONENTS_KEY: &'static str = "responses";
+
+    fn lookup<'a>(
+        components: &'a openapiv3::Components,
+        name: &str,
+    ) -> Option<&'a ReferenceOr<Self>> {
+        components.responses.get(name)
+    }
+
+    fn malformed_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MalformedResponseBodyReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+
+    fn missing_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MissingResponseBodyReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+}
+
+impl Component for openapiv3::Parameter {
+    const COMPONENTS_KEY: &'static str = "parameters";
+
+    fn lookup<'a>(
+        components: &'a openapiv3::Components,
+        name: &str,
+    ) -> Option<&'a ReferenceOr<Self>> {
+        components.parameters.get(name)
+    }
+
+    fn malformed_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MalformedParameterReference {
+            operation: diagnostic_context.operation.clone(),
+            path: diagnostic_context.path.clone(),
+            reference: reference.to_string(),
+        }
+    }
+
+    fn missing_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MissingParameterReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+}
+
+impl Component for openapiv3::SecurityScheme {
+    const COMPONENTS_KEY: &'static str = "securitySchemes";
+
+    fn lookup<'a>(
+        components: &'a openapiv3::Components,
+        name: &str,
+    ) -> Option<&'a ReferenceOr<Self>> {
+        components.security_schemes.get(name)
+    }
+
+    fn malformed_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MalformedSecuritySchemeReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+
+    fn missing_reference(diagnostic_context: &DiagnosticContext, reference: &str) -> HeaveError {
+        HeaveError::MissingSecuritySchemeReference {
+            context: diagnostic_context.clone(),
+            reference: reference.to_string(),
+        }
+    }
+}
+
+/// Resolves the parameters that apply to an operation.
+///
+/// Parameters defined on the path item are shared by every operation on that path. An operation
+/// can override one of them by defining a parameter with the same name and location, in which
+/// case only the operation's parameter is used. Path item parameters come first, followed by the
+/// operation's own parameters.
+fn resolve_parameters<'a>(
+    spec: &'a Spec,
+    path_item: Option<&'a openapiv3::PathItem>,
+    operation: &'a openapiv3::Operation,
     diagnostic_context: &DiagnosticContext,
-) -> (Option<&'a openapiv3::Schema>, Vec<HeaveError>) {
+) -> (Vec<Cow<'a, openapiv3::Parameter>>, Vec<HeaveError>) {
     let mut diagnostics: Vec<HeaveError> = vec![];
-    match schema {
-        ReferenceOr::Item(item) => (Some(item), diagnostics),
-        ReferenceOr::Reference { reference } => {
-            let schema_name = reference.split("#/components/schemas/").nth(1);
-            if schema_name.is_none() {
-                diagnostics.push(HeaveError::MalformedSchemaReference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let schema_name = schema_name.unwrap();
-            let components = &openapi.components;
-            if components.is_none() {
-                diagnostics.push(HeaveError::MissingComponents);
-                return (None, diagnostics);
-            }
-            let found_schema = components.as_ref().unwrap().schemas.get(schema_name);
-            if found_schema.is_none() {
-                diagnostics.push(HeaveError::MissingSchemaReference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let found_schema = found_schema.unwrap();
-            if found_schema.as_item().is_none() {
-                diagnostics.push(HeaveError::FailedSchemaDereference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let schema = found_schema.as_item().unwrap();
-            (Some(schema), diagnostics)
+    let mut operation_parameters = vec![];
+    for parameter in operation.parameters.iter() {
+        let (parameter, mut inner_diagnostics) =
+            resolve_reference(spec, parameter, diagnostic_context);
+        diagnostics.append(&mut inner_diagnostics);
+        operation_parameters.extend(parameter);
+    }
+
+    let is_same_parameter = |a: &openapiv3::Parameter, b: &openapiv3::Parameter| {
+        std::mem::discriminant(a) == std::mem::discriminant(b)
+            && a.parameter_data_ref().name == b.parameter_data_ref().name
+    };
+    let mut parameters = vec![];
+    for parameter in path_item.iter().flat_map(|p| p.parameters.iter()) {
+        let (parameter, mut inner_diagnostics) =
+            resolve_reference(spec, parameter, diagnostic_context);
+        diagnostics.append(&mut inner_diagnostics);
+        if parameter.is_none() {
+            continue;
         }
+        let parameter = parameter.unwrap();
+        if operation_parameters
+            .iter()
+            .any(|p| is_same_parameter(p, &parameter))
+        {
+            continue;
+        }
+        parameters.push(parameter);
     }
+    parameters.append(&mut operation_parameters);
+    (parameters, diagnostics)
 }
 
-fn resolve_request_body<'a>(
-    openapi: &'a openapiv3::OpenAPI,
-    request_body: &'a openapiv3::ReferenceOr<openapiv3::RequestBody>,
+/// Resolves a component, following references until an actual definition is found.
+///
+/// Components are allowed to be aliases of other components (e.g. `UserId` referencing `Uuid`)
+/// so chains of any length are followed. A chain that leads back to a reference that was already
+/// visited is reported as a cycle. References to other files are loaded through the `Spec`, which
+/// is why the result may be owned rather than borrowed from the spec.
+fn resolve_reference<'a, T: Component>(
+    spec: &'a Spec,
+    reference_or: &'a ReferenceOr<T>,
     diagnostic_context: &DiagnosticContext,
-) -> (Option<&'a openapiv3::RequestBody>, Vec<HeaveError>) {
+) -> (Option<Cow<'a, T>>, Vec<HeaveError>) {
     let mut diagnostics: Vec<HeaveError> = vec![];
-    match request_body {
-        ReferenceOr::Item(item) => (Some(item), diagnostics),
-        ReferenceOr::Reference { reference } => {
-            let request_body_name = reference.split("#/components/requestBodies/").nth(1);
-            if request_body_name.is_none() {
-                diagnostics.push(HeaveError::MalformedRequestBodyReference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let request_body_name = request_body_name.unwrap();
-            let components = &openapi.components;
-            if components.is_none() {
-                diagnostics.push(HeaveError::MissingComponents);
-                return (None, diagnostics);
+    let mut visited_references: Vec<String> = vec![];
+    let mut current = Cow::Borrowed(reference_or);
+    loop {
+        let reference = match current {
+            Cow::Borrowed(ReferenceOr::Item(item)) => {
+                return (Some(Cow::Borrowed(item)), diagnostics)
             }
-            let found_request_body = components
-                .as_ref()
-                .unwrap()
-                .request_bodies
-                .get(request_body_name);
-            if found_request_body.is_none() {
-                diagnostics.push(HeaveError::MissingRequestBodyReference {
+            Cow::Owned(ReferenceOr::Item(item)) => return (Some(Cow::Owned(item)), diagnostics),
+            Cow::Borrowed(ReferenceOr::Reference { reference }) => reference.to_string(),
+            Cow::Owned(ReferenceOr::Reference { reference }) => reference,
+        };
+        if visited_references.contains(&reference) {
+            visited_references.push(reference);
+            diagnostics.push(HeaveError::CircularReference {
+                context: diagnostic_context.clone(),
+                references: visited_references.join(" -> "),
+            });
+            return (None, diagnostics);
+        }
+        visited_references.push(reference.clone());
+
+        if spec::is_external_reference(&reference) {
+            let found_component = match spec.resolve_external(&reference) {
+                Ok(value) => serde_json::from_value::<ReferenceOr<T>>(value).ok(),
+                Err(ExternalReferenceError::MissingFile { file, cause }) => {
+                    diagnostics.push(HeaveError::MissingExternalFile {
+                        context: diagnostic_context.clone(),
+                        reference,
+                        file,
+                        cause,
+                    });
+                    return (None, diagnostics);
+                }
+                Err(ExternalReferenceError::UnreadablePointer) => None,
+            };
+            if found_component.is_none() {
+                diagnostics.push(HeaveError::UnreadableExternalPointer {
                     context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
+                    reference,
                 });
                 return (None, diagnostics);
             }
-            let found_request_body = found_request_body.unwrap();
-            if found_request_body.as_item().is_none() {
-                diagnostics.push(HeaveError::FailedRequestBodyDereference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
+            current = Cow::Owned(found_component.unwrap());
+            continue;
+        }
+
+        let tokens = spec::parse_json_pointer(&reference);
+        if tokens.is_none() {
+            diagnostics.push(T::malformed_reference(diagnostic_context, &reference));
+            return (None, diagnostics);
+        }
+        let tokens = tokens.unwrap();
+        let components = &spec.openapi.components;
+        if tokens.first().is_some_and(|t| t == "components") && components.is_none() {
+            diagnostics.push(HeaveError::MissingComponents);
+            return (None, diagnostics);
+        }
+        // Pointers to a component are looked up directly so that the result can be borrowed.
+        // Everything else (e.g. `#/components/schemas/Pet/properties/tags`) is found by walking
+        // the whole document.
+        if let [_, kind, name] = tokens.as_slice() {
+            if tokens[0] == "components" && kind == T::COMPONENTS_KEY {
+                let found_component = T::lookup(components.as_ref().unwrap(), name);
+                if found_component.is_none() {
+                    diagnostics.push(T::missing_reference(diagnostic_context, &reference));
+                    return (None, diagnostics);
+                }
+                current = Cow::Borrowed(found_component.unwrap());
+                continue;
             }
-            let request_body = found_request_body.as_item().unwrap();
-            (Some(request_body), diagnostics)
         }
+        let found_component = spec
+            .resolve_pointer(&tokens)
+            .and_then(|value| serde_json::from_value::<ReferenceOr<T>>(value).ok());
+        if found_component.is_none() {
+            diagnostics.push(T::missing_reference(diagnostic_context, &reference));
+            return (None, diagnostics);
+        }
+        current = Cow::Owned(found_component.unwrap());
     }
 }
 
 fn generate_request_body_from_schema(
-    openapi: &openapiv3::OpenAPI,
+    spec: &Spec,
     schema: &openapiv3::Schema,
     name: Option<String>,
     diagnostic_context: &DiagnosticContext,
@@ -1144,7 +2292,7 @@ fn generate_request_body_from_schema(
         }
         // Check if the immediate next part is the same as the current part. If it is, we have a
         // cycle
-        if parts.peek().map_or(false, |next| *next == part) {
+        if parts.peek().is_some_and(|next| *next == part) {
             return (
                 None,
                 vec![HeaveError::RequestBodySchemaCycleDetected {
@@ -1156,7 +2304,7 @@ fn generate_request_body_from_schema(
         // Check the next part
         let mut peek_again = parts.clone();
         let _ = peek_again.next();
-        if peek_again.next().map_or(false, |next| next == part) {
+        if peek_again.next().is_some_and(|next| next == part) {
             return (
                 None,
                 vec![HeaveError::RequestBodySchemaCycleDetected {
@@ -1169,61 +2317,80 @@ fn generate_request_body_from_schema(
 
     let mut diagnostics = vec![];
     match &schema.schema_kind {
-        openapiv3::SchemaKind::OneOf { .. } => {
-            diagnostics.push(HeaveError::UnsupportedSchemaKind {
-                context: diagnostic_context.clone(),
-                kind: "OneOf".to_string(),
-                jsonpath: name.unwrap_or("".to_string()),
-            })
+        openapiv3::SchemaKind::OneOf { one_of: branches }
+        | openapiv3::SchemaKind::AnyOf { any_of: branches } => {
+            // Top level oneOf request bodies, and anyOf ones with a discriminator, are split into
+            // variants before reaching this point. Others use the first branch with a body.
+            let discriminator = schema.schema_data.discriminator.as_ref();
+            let (schema_variants, mut inner_diagnostics) =
+                resolve_schema_variants(spec, branches, discriminator, diagnostic_context);
+            diagnostics.append(&mut inner_diagnostics);
+            for schema_variant in schema_variants {
+                let (request_body, mut inner_diagnostics) = generate_request_body_from_schema(
+                    spec,
+                    &schema_variant.schema,
+                    None,
+                    diagnostic_context,
+                    jsonpath,
+                );
+                diagnostics.append(&mut inner_diagnostics);
+                if let Some(mut body) = request_body {
+                    if let (Some(discriminator), Some(value)) =
+                        (discriminator, &schema_variant.discriminator_value)
+                    {
+                        body = apply_discriminator_value(body, &discriminator.property_name, value);
+                    }
+                    return match name {
+                        Some(name) => (Some(format!("\"{}\": {}", name, body)), diagnostics),
+                        None => (Some(body), diagnostics),
+                    };
+                }
+            }
         }
         openapiv3::SchemaKind::AllOf { all_of } => {
-            let mut child_request_bodies = vec![];
-            let mut flattened_object_fields = serde_json::Value::Object(serde_json::Map::new());
+            // Every branch describes the same value. Objects are flattened into a single object,
+            // and otherwise the first primitive is used. A value can't be both, so the object wins.
+            let mut primitive_body: Option<String> = None;
+            let mut flattened_object_fields = serde_json::Map::new();
             for all_of_schema_or_ref in all_of {
                 let (all_of_schema, mut inner_diagnostics) =
-                    resolve_schema(openapi, all_of_schema_or_ref, diagnostic_context);
+                    resolve_reference(spec, all_of_schema_or_ref, diagnostic_context);
                 diagnostics.append(&mut inner_diagnostics);
-                if let Some(s) = all_of_schema {
-                    let (request_body, mut inner_diagnostics) = generate_request_body_from_schema(
-                        openapi,
-                        s,
-                        None,
-                        diagnostic_context,
-                        jsonpath,
-                    );
-                    diagnostics.append(&mut inner_diagnostics);
-
-                    if let Some(body) = &request_body {
-                        // In the case of `allOf`, objects need special handling. We create an
-                        // empty JSON value and then flatten all the fields on to that single
-                        // value. Any primitive fields can just be added directly to
-                        // `child_request_bodies`.
-                        let mut j = serde_json::from_str::<serde_json::Value>(body).unwrap();
-                        if j.is_object() {
-                            let inner_map = flattened_object_fields.as_object_mut().unwrap();
-                            inner_map.append(j.as_object_mut().unwrap());
-                        } else {
-                            child_request_bodies.push(request_body);
-                        }
+                if all_of_schema.is_none() {
+                    continue;
+                }
+                let s = all_of_schema.unwrap();
+                // Branches without a type only add annotations (e.g. `description`) to the others
+                if let openapiv3::SchemaKind::Any(any) = &s.schema_kind {
+                    if infer_schema_from_any(&s.schema_data, any).is_none() {
+                        continue;
+                    }
+                }
+                let (request_body, mut inner_diagnostics) =
+                    generate_request_body_from_schema(spec, &s, None, diagnostic_context, jsonpath);
+                diagnostics.append(&mut inner_diagnostics);
+                if request_body.is_none() {
+                    continue;
+                }
+                let body = request_body.unwrap();
+                match serde_json::from_str::<serde_json::Value>(&body) {
+                    Ok(serde_json::Value::Object(mut object)) => {
+                        flattened_object_fields.append(&mut object)
+                    }
+                    _ => {
+                        primitive_body.get_or_insert(body);
                     }
                 }
-            }
-            // Only include `flattened_object_fields` if we actually added anything to it.
-            if !flattened_object_fields.as_object().unwrap().is_empty() {
-                child_request_bodies.push(Some(flattened_object_fields.to_string()));
             }
 
-            // If child_request_bodies is empty we need to communicate that we couldn't build
-            // anything.
-            if child_request_bodies.is_empty() {
+            let stringified_body = if !flattened_object_fields.is_empty() {
+                serde_json::Value::Object(flattened_object_fields).to_string()
+            } else if let Some(body) = primitive_body {
+                body
+            } else {
+                // Nothing could be built from any of the branches
                 return (None, diagnostics);
-            }
-
-            let stringified_body = child_request_bodies
-                .into_iter()
-                .flatten()
-                .collect::<Vec<String>>()
-                .join(",\n");
+            };
 
             return match name {
                 Some(name) => (
@@ -1233,23 +2400,27 @@ fn generate_request_body_from_schema(
                 None => (Some(stringified_body), diagnostics),
             };
         }
-        openapiv3::SchemaKind::AnyOf { .. } => {
-            diagnostics.push(HeaveError::UnsupportedSchemaKind {
-                context: diagnostic_context.clone(),
-                kind: "AnyOf".to_string(),
-                jsonpath: name.unwrap_or("".to_string()),
-            })
-        }
         openapiv3::SchemaKind::Not { .. } => diagnostics.push(HeaveError::UnsupportedSchemaKind {
             context: diagnostic_context.clone(),
             kind: "Not".to_string(),
             jsonpath: name.unwrap_or("".to_string()),
         }),
-        openapiv3::SchemaKind::Any(_) => diagnostics.push(HeaveError::UnsupportedSchemaKind {
-            context: diagnostic_context.clone(),
-            kind: "Any".to_string(),
-            jsonpath: name.unwrap_or("".to_string()),
-        }),
+        openapiv3::SchemaKind::Any(any) => {
+            return match infer_schema_from_any(&schema.schema_data, any) {
+                Some(inferred) => generate_request_body_from_schema(
+                    spec,
+                    &inferred,
+                    name,
+                    diagnostic_context,
+                    jsonpath,
+                ),
+                // A free-form schema allows any value, so `null` is left as a placeholder.
+                None => match name {
+                    Some(name) => (Some(format!("\"{}\": null", name)), diagnostics),
+                    None => (Some("null".to_string()), diagnostics),
+                },
+            };
+        }
         openapiv3::SchemaKind::Type(schema_type) => {
             // A small helper that takes properties that may or may not have names and formats them
             // accordingly. If they have a name, start by indenting them, print the named property,
@@ -1260,6 +2431,12 @@ fn generate_request_body_from_schema(
                     None => default.to_string(),
                 }
             };
+            if let Some(value) = const_value(schema_type) {
+                return (
+                    Some(single_property_formatter(name, &value.to_string())),
+                    diagnostics,
+                );
+            }
             return match schema_type {
                 openapiv3::Type::Boolean(_) => {
                     (Some(single_property_formatter(name, "false")), diagnostics)
@@ -1276,7 +2453,7 @@ fn generate_request_body_from_schema(
                     for (name, prop) in properties.iter() {
                         let unboxed = prop.clone().unbox();
                         let (inner, mut inner_diagnostics) =
-                            resolve_schema(openapi, &unboxed, diagnostic_context);
+                            resolve_reference(spec, &unboxed, diagnostic_context);
                         diagnostics.append(&mut inner_diagnostics);
                         if inner.is_none() {
                             return (None, diagnostics);
@@ -1284,8 +2461,8 @@ fn generate_request_body_from_schema(
                         let inner = inner.unwrap();
                         let (request_body, mut inner_diagnostics) =
                             generate_request_body_from_schema(
-                                openapi,
-                                inner,
+                                spec,
+                                &inner,
                                 Some(name.to_string()),
                                 diagnostic_context,
                                 format!("{}.{}", jsonpath, name).as_ref(),
@@ -1314,7 +2491,7 @@ fn generate_request_body_from_schema(
                     let items = items.as_ref().unwrap();
                     let unboxed = items.clone().unbox();
                     let (inner, mut inner_diagnostics) =
-                        resolve_schema(openapi, &unboxed, diagnostic_context);
+                        resolve_reference(spec, &unboxed, diagnostic_context);
                     diagnostics.append(&mut inner_diagnostics);
                     if inner.is_none() {
                         return (None, diagnostics);
@@ -1322,8 +2499,8 @@ fn generate_request_body_from_schema(
                     let inner = inner.unwrap();
                     let (child_request_body, mut child_diagnostics) =
                         generate_request_body_from_schema(
-                            openapi,
-                            inner,
+                            spec,
+                            &inner,
                             None,
                             diagnostic_context,
                             format!("{}[]", jsonpath).as_ref(),
@@ -1347,59 +2524,25 @@ fn generate_request_body_from_schema(
     (None, diagnostics)
 }
 
-fn resolve_response<'a>(
-    openapi: &'a openapiv3::OpenAPI,
-    response: &'a openapiv3::ReferenceOr<openapiv3::Response>,
-    diagnostic_context: &DiagnosticContext,
-) -> (Option<&'a openapiv3::Response>, Vec<HeaveError>) {
-    let mut diagnostics = vec![];
-    match response {
-        ReferenceOr::Item(item) => (Some(item), diagnostics),
-        ReferenceOr::Reference { reference } => {
-            let response_name = reference.split("#/components/responses/").nth(1);
-            if response_name.is_none() {
-                diagnostics.push(HeaveError::MalformedResponseBodyReference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let response_name = response_name.unwrap();
-            let components = &openapi.components;
-            if components.is_none() {
-                diagnostics.push(HeaveError::MissingComponents);
-                return (None, diagnostics);
-            }
-            let found_response = components.as_ref().unwrap().responses.get(response_name);
-            if found_response.is_none() {
-                diagnostics.push(HeaveError::MissingResponseBodyReference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let found_response = found_response.unwrap();
-            if found_response.as_item().is_none() {
-                diagnostics.push(HeaveError::FailedResponseBodyDereference {
-                    context: diagnostic_context.clone(),
-                    reference: reference.to_string(),
-                });
-                return (None, diagnostics);
-            }
-            let schema = found_response.as_item().unwrap();
-            (Some(schema), diagnostics)
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use std::{error::Error, path::PathBuf, str::FromStr};
+    use std::{
+        error::Error,
+        path::{Path, PathBuf},
+        str::FromStr,
+    };
+
+    use clap::Parser;
 
     use insta::{assert_debug_snapshot, assert_snapshot, glob};
     use openapiv3::OpenAPI;
 
-    use crate::{generate, write_outputs, Output, DEFAULT_HURL_TEMPLATE};
+    use crate::config::Config;
+    use crate::input::InputSource;
+    use crate::overlay::Overlay;
+    use crate::{
+        generate, write_outputs, Cli, Commands, HeaveError, Output, Spec, DEFAULT_HURL_TEMPLATE,
+    };
 
     // Creates an OpenAPI from a file path
     macro_rules! openapi_from_yaml {
@@ -1414,7 +2557,7 @@ mod tests {
         let content = std::fs::read_to_string("src/snapshots/petstore/petstore.yaml")?;
         let openapi: OpenAPI = serde_yaml::from_str(&content).expect("Could not deserialize input");
         let output_directory = PathBuf::from_str("src/snapshots/petstore")?;
-        let result = generate(openapi);
+        let result = generate(&mut Spec::new(openapi));
         write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
         let mut settings = insta::Settings::clone_current();
         settings.set_omit_expression(true);
@@ -1430,7 +2573,7 @@ mod tests {
         let content = std::fs::read_to_string("src/snapshots/petstore/petstore.json")?;
         let openapi: OpenAPI = serde_json::from_str(&content).expect("Could not deserialize input");
         let output_directory = PathBuf::from_str("src/snapshots/petstore")?;
-        let result = generate(openapi);
+        let result = generate(&mut Spec::new(openapi));
         write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
         let mut settings = insta::Settings::clone_current();
         settings.set_omit_expression(true);
@@ -1453,7 +2596,68 @@ mod tests {
         settings.bind(|| {
             glob!("snapshots/diagnostics/*.yaml", |path| {
                 let input: OpenAPI = openapi_from_yaml!(&path);
-                let result = generate(input);
+                let result = generate(&mut Spec::new(input));
+                assert_debug_snapshot!(result);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn references() -> Result<(), Box<dyn Error>> {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/references/*.yaml", |path| {
+                let input: OpenAPI = openapi_from_yaml!(&path);
+                let result = generate(&mut Spec::new(input));
+                assert_debug_snapshot!(result);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn path_item_parameters() -> Result<(), Box<dyn Error>> {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/path_item_parameters/*.yaml", |path| {
+                let input: OpenAPI = openapi_from_yaml!(&path);
+                let result = generate(&mut Spec::new(input));
+                assert_debug_snapshot!(result);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn parse_failures() -> Result<(), Box<dyn Error>> {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/parse_failures/*.{yaml,json}", |path| {
+                let content = std::fs::read_to_string(path).unwrap();
+                let relative_path = path.strip_prefix(env!("CARGO_MANIFEST_DIR")).unwrap();
+                let location = InputSource::File(relative_path.to_path_buf());
+                let error = Spec::load(&content, &location, &[]).err().unwrap();
+                assert_snapshot!(error.to_string());
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn input_formats() -> Result<(), Box<dyn Error>> {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            // The format comes from the content, so the extension doesn't matter
+            glob!("snapshots/input_formats/*", |path| {
+                let content = std::fs::read_to_string(path).unwrap();
+                let location = InputSource::File(path.to_path_buf());
+                let mut spec = Spec::load(&content, &location, &[]).unwrap();
+                let result = generate(&mut spec);
                 assert_debug_snapshot!(result);
             });
         });
@@ -1467,7 +2671,7 @@ mod tests {
         settings.bind(|| {
             glob!("snapshots/cycle_detection/*.yaml", |path| {
                 let input: OpenAPI = openapi_from_yaml!(&path);
-                let result = generate(input);
+                let result = generate(&mut Spec::new(input));
                 assert_debug_snapshot!(result);
             });
         });
@@ -1481,7 +2685,7 @@ mod tests {
         settings.bind(|| {
             glob!("snapshots/read_only/*.yaml", |path| {
                 let input: OpenAPI = openapi_from_yaml!(&path);
-                let result = generate(input);
+                let result = generate(&mut Spec::new(input));
                 assert_debug_snapshot!(result);
             });
         });
@@ -1495,7 +2699,7 @@ mod tests {
         settings.bind(|| {
             glob!("snapshots/write_only/*.yaml", |path| {
                 let input: OpenAPI = openapi_from_yaml!(&path);
-                let result = generate(input);
+                let result = generate(&mut Spec::new(input));
                 assert_debug_snapshot!(result);
             });
         });
@@ -1506,7 +2710,7 @@ mod tests {
     fn allof_inputs() -> Result<(), Box<dyn Error>> {
         let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/allof/petstore.yaml");
         let output_directory = PathBuf::from_str("src/snapshots/allof")?;
-        let result = generate(openapi);
+        let result = generate(&mut Spec::new(openapi));
         write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
         let mut settings = insta::Settings::clone_current();
         settings.set_omit_expression(true);
@@ -1519,6 +2723,418 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn one_of_inputs() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/one_of/payments.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/one_of")?;
+        let result = generate(&mut Spec::new(openapi));
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/one_of/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn openapi31_inputs() -> Result<(), Box<dyn Error>> {
+        let content = std::fs::read_to_string("src/snapshots/openapi31/pets.yaml")?;
+        let document: serde_json::Value = serde_yaml::from_str(&content)?;
+        let output_directory = PathBuf::from_str("src/snapshots/openapi31")?;
+        let result = generate(&mut Spec::from_document(document)?);
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/openapi31/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+            assert_debug_snapshot!(result.diagnostics);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn swagger2_inputs() -> Result<(), Box<dyn Error>> {
+        let content = std::fs::read_to_string("src/snapshots/swagger2/petstore.yaml")?;
+        let document: serde_json::Value = serde_yaml::from_str(&content)?;
+        let (converted, _) = crate::swagger2::convert(&document);
+        let result = generate(&mut Spec::from_document(document)?);
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            assert_snapshot!(serde_yaml::to_string(&converted).unwrap());
+            assert_debug_snapshot!(result);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn discriminator_inputs() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/discriminator/payments.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/discriminator")?;
+        let result = generate(&mut Spec::new(openapi));
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/discriminator/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn any_of_inputs() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/any_of/pets.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/any_of")?;
+        let result = generate(&mut Spec::new(openapi));
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/any_of/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn any_schema_inputs() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/any_schema/pets.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/any_schema")?;
+        let result = generate(&mut Spec::new(openapi));
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/any_schema/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn external_references() -> Result<(), Box<dyn Error>> {
+        let input_path = PathBuf::from_str("src/snapshots/external/petstore.yaml")?;
+        let openapi: OpenAPI = openapi_from_yaml!(&input_path);
+        let output_directory = PathBuf::from_str("src/snapshots/external")?;
+        let location = InputSource::File(input_path);
+        let result = generate(&mut Spec::new(openapi).with_location(&location));
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/external/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+            assert_debug_snapshot!(result.diagnostics);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_inputs() -> Result<(), Box<dyn Error>> {
+        let inputs = crate::input::expand_globs(vec![
+            InputSource::from_str("src/snapshots/path_item_parameters/pets.yaml")?,
+            InputSource::from_str("src/snapshots/input_formats/*")?,
+            InputSource::from_str("https://example.com/users/openapi.json")?,
+            InputSource::from_str("https://example.com/orders/openapi.json")?,
+            InputSource::from_str("-")?,
+        ])?;
+        assert_eq!(
+            crate::input::namespaces(&inputs),
+            vec![
+                "path_item_parameters",
+                "input_formats",
+                "input_formats_2",
+                "users",
+                "orders",
+                "stdin",
+            ]
+        );
+        assert!(
+            crate::input::expand_globs(vec![InputSource::from_str("missing/*.yaml")?]).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn config() -> Result<(), Box<dyn Error>> {
+        let config = Config::load(Path::new("src/snapshots/config/heave.toml"))?;
+        // The command line overrides the config file
+        let cli = Cli::try_parse_from(["heave", "generate", "--include-paths", "pets"])?;
+        let Commands::Generate(args) = cli.command else {
+            panic!("Expected the generate command");
+        };
+        let plan = crate::config::plan(args, Some(config))?;
+
+        let error = Config::load(Path::new("src/snapshots/config/unknown_key.toml"))
+            .err()
+            .unwrap();
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            assert_debug_snapshot!(plan);
+            assert_snapshot!(error.to_string());
+        });
+
+        // Flags the config file turns on can be turned off on the command line
+        let config = Config::load(Path::new("src/snapshots/config/heave.toml"))?;
+        let cli =
+            Cli::try_parse_from(["heave", "generate", "--no-only-new", "--no-negative-auth"])?;
+        let Commands::Generate(args) = cli.command else {
+            panic!("Expected the generate command");
+        };
+        let plan = crate::config::plan(args, Some(config))?;
+        assert!(plan.specs.iter().all(|s| !s.only_new && !s.negative_auth));
+        Ok(())
+    }
+
+    #[test]
+    fn init() -> Result<(), Box<dyn Error>> {
+        let location = InputSource::from_str("src/snapshots/petstore/petstore.yaml")?;
+        let spec = Spec::load(&location.read()?, &location, &[])?;
+        let files = crate::init::scaffold("specs/petstore.yaml", spec);
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            for (file, content) in files.iter() {
+                if content == DEFAULT_HURL_TEMPLATE {
+                    continue;
+                }
+                let name = format!("init_{}", file.display());
+                assert_snapshot!(name, content);
+            }
+        });
+        assert!(files.iter().any(
+            |(file, content)| file.starts_with("templates") && content == DEFAULT_HURL_TEMPLATE
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn variables_inputs() -> Result<(), Box<dyn Error>> {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/variables/*.yaml", |path| {
+                let input: OpenAPI = openapi_from_yaml!(&path);
+                let mut spec = Spec::new(input);
+                let result = generate(&mut spec);
+                let variables = crate::variables::collect(&spec, &result.outputs);
+                assert_snapshot!(crate::variables::render(
+                    crate::variables::VARIABLES_FILE,
+                    &variables
+                ));
+            });
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn security() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/security/security.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/security")?;
+        let mut spec = Spec::new(openapi);
+        let result = generate(&mut spec);
+        let outputs = crate::security::add_negative_outputs(result.outputs.clone());
+        write_outputs(&outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let variables = crate::variables::collect(&spec, &result.outputs);
+        let diagnostics: Vec<String> = result.diagnostics.iter().map(|d| d.to_string()).collect();
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/security/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+            assert_snapshot!(crate::variables::render(
+                crate::variables::VARIABLES_FILE,
+                &variables
+            ));
+            assert_debug_snapshot!(diagnostics);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn parameter_locations() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI =
+            openapi_from_yaml!("src/snapshots/parameter_locations/parameter_locations.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/parameter_locations")?;
+        let result = generate(&mut Spec::new(openapi));
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let diagnostics: Vec<String> = result.diagnostics.iter().map(|d| d.to_string()).collect();
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/parameter_locations/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+            assert_debug_snapshot!(diagnostics);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn path_examples() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/variables/pets.yaml");
+        let mut spec = Spec::new(openapi);
+        let result = generate(&mut spec);
+        let path_parameters: Vec<_> = result
+            .outputs
+            .iter()
+            .map(|o| (&o.name, &o.path_parameters))
+            .collect();
+        let outputs = crate::substitute_path_examples(result.outputs.clone());
+        let paths: Vec<&str> = outputs.iter().map(|o| o.hurl_path.as_str()).collect();
+        // Only the parameters without an example are still variables
+        let variables = crate::variables::collect(&spec, &outputs);
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            assert_debug_snapshot!(path_parameters);
+            assert_debug_snapshot!(paths);
+            assert_snapshot!(crate::variables::render(
+                crate::variables::VARIABLES_FILE,
+                &variables
+            ));
+            assert_debug_snapshot!(result.diagnostics);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn servers() -> Result<(), Box<dyn Error>> {
+        let openapi: OpenAPI = openapi_from_yaml!("src/snapshots/servers/servers.yaml");
+        let output_directory = PathBuf::from_str("src/snapshots/servers")?;
+        let mut spec = Spec::new(openapi);
+        let result = generate(&mut spec);
+        write_outputs(&result.outputs, DEFAULT_HURL_TEMPLATE, &output_directory)?;
+        let environments = crate::variables::environments(&spec, &result.outputs);
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            glob!("snapshots/servers/*.hurl", |path| {
+                let input = std::fs::read_to_string(path).unwrap();
+                assert_snapshot!(input);
+            });
+            for (file, variables) in environments.iter() {
+                let name = format!("servers_{}", file);
+                assert_snapshot!(name, crate::variables::render(file, variables));
+            }
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn overlays() -> Result<(), Box<dyn Error>> {
+        let location = InputSource::from_str("src/snapshots/overlays/pets.yaml")?;
+        let overlay_location = InputSource::from_str("src/snapshots/overlays/overlay.yaml")?;
+        let overlay = Overlay::load(&overlay_location.read()?, &overlay_location)?;
+        let mut spec = Spec::load(&location.read()?, &location, &[overlay])?;
+        let result = generate(&mut spec);
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            assert_debug_snapshot!(result);
+        });
+        Ok(())
+    }
+
+    /// Serves the files in `directory` over HTTP until the test ends, returning the base URL.
+    fn serve_directory(directory: &'static str) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(&stream);
+                let mut request_line = String::new();
+                std::io::BufRead::read_line(&mut reader, &mut request_line).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let response = match std::fs::read_to_string(format!("{}{}", directory, path)) {
+                    Ok(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    Err(_) => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                std::io::Write::write_all(&mut stream, response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn url_inputs() -> Result<(), Box<dyn Error>> {
+        let base_url = serve_directory("src/snapshots/external");
+        let location = InputSource::from_str(&format!("{}/petstore.yaml", base_url))?;
+        let content = location.read()?;
+        let result = generate(&mut Spec::load(&content, &location, &[])?);
+
+        // Relative references are fetched from the same server, so the result matches reading
+        // the spec from disk.
+        let input_path = PathBuf::from_str("src/snapshots/external/petstore.yaml")?;
+        let openapi: OpenAPI = openapi_from_yaml!(&input_path);
+        let expected =
+            generate(&mut Spec::new(openapi).with_location(&InputSource::File(input_path)));
+        assert_eq!(
+            format!("{:?}", result.outputs),
+            format!("{:?}", expected.outputs)
+        );
+        // The spec refers to a file that doesn't exist, which the server reports as a 404
+        assert_eq!(result.diagnostics.len(), expected.diagnostics.len());
+
+        let missing = InputSource::from_str(&format!("{}/missing.yaml", base_url))?;
+        assert!(missing.read().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn bundle() -> Result<(), Box<dyn Error>> {
+        let location = InputSource::from_str("src/snapshots/bundle/petstore.yaml")?;
+        let content = location.read()?;
+        let document: serde_json::Value = serde_yaml::from_str(&content)?;
+        let (bundled, diagnostics) = crate::bundle::bundle(document, &location);
+        assert!(diagnostics.is_empty());
+
+        // The bundled spec must not need any other files to generate hurl files. `Pet.parent`
+        // refers back to `Pet`, which is reported as a cycle.
+        let openapi: OpenAPI = serde_json::from_value(bundled.clone())?;
+        let result = generate(&mut Spec::new(openapi));
+        assert_eq!(result.outputs.len(), 4);
+        assert!(result
+            .diagnostics
+            .iter()
+            .all(|d| matches!(d, HeaveError::ResponseBodySchemaCycleDetected { .. })));
+
+        let mut settings = insta::Settings::clone_current();
+        settings.set_omit_expression(true);
+        settings.bind(|| {
+            assert_snapshot!(serde_yaml::to_string(&bundled).unwrap());
+        });
+        Ok(())
+    }
+
     #[test]
     fn filter_only_new_outputs() {
         let existing_files = vec![
@@ -1528,14 +3144,20 @@ mod tests {
         let out1 = Output {
             name: "file1.hurl".to_string(),
             method: "GET".to_string(),
+            path_parameters: vec![],
+            base_url_variable: "baseurl".to_string(),
+            authentication: Default::default(),
             expected_status_code: 0,
             hurl_path: "".to_string(),
             oas_path: "".to_string(),
             oas_operation_id: None,
             header_parameters: vec![],
             query_parameters: vec![],
+            cookie_parameters: vec![],
             asserts: vec![],
             request_body_parameter: "".to_string(),
+            request_body_variant: None,
+            response_body_variant: None,
         };
         let out2 = Output {
             name: "file2.hurl".to_string(),
@@ -1557,14 +3179,20 @@ mod tests {
         let out1 = Output {
             name: "get_documents_200.hurl".to_string(),
             method: "GET".to_string(),
+            path_parameters: vec![],
+            base_url_variable: "baseurl".to_string(),
+            authentication: Default::default(),
             expected_status_code: 200,
             hurl_path: "".to_string(),
             oas_path: "/documents".to_string(),
             oas_operation_id: None,
             header_parameters: vec![],
             query_parameters: vec![],
+            cookie_parameters: vec![],
             asserts: vec![],
             request_body_parameter: "".to_string(),
+            request_body_variant: None,
+            response_body_variant: None,
         };
         let out2 = Output {
             name: "get_document_by_id_200.hurl".to_string(),
@@ -1582,14 +3210,20 @@ mod tests {
         let out1 = Output {
             name: "get_documents_200.hurl".to_string(),
             method: "GET".to_string(),
+            path_parameters: vec![],
+            base_url_variable: "baseurl".to_string(),
+            authentication: Default::default(),
             expected_status_code: 200,
             hurl_path: "".to_string(),
             oas_path: "/documents".to_string(),
             oas_operation_id: None,
             header_parameters: vec![],
             query_parameters: vec![],
+            cookie_parameters: vec![],
             asserts: vec![],
             request_body_parameter: "".to_string(),
+            request_body_variant: None,
+            response_body_variant: None,
         };
         let out2 = Output {
             name: "get_document_by_id_400.hurl".to_string(),
@@ -1618,7 +3252,7 @@ mod tests {
             out4.clone(),
             out5.clone(),
         ];
-        let regexes = vec![
+        let regexes = [
             regex_lite::Regex::new("^2\\d{2}$").unwrap(),
             regex_lite::Regex::new("^4\\d{2}$").unwrap(),
             regex_lite::Regex::new("^200|400").unwrap(),
@@ -1647,14 +3281,20 @@ mod tests {
         let out1 = Output {
             name: "addPet_200.hurl".to_string(),
             method: "".to_string(),
+            path_parameters: vec![],
+            base_url_variable: "baseurl".to_string(),
+            authentication: Default::default(),
             expected_status_code: 200,
             hurl_path: "".to_string(),
             oas_path: "".to_string(),
             oas_operation_id: None,
             header_parameters: vec![],
             query_parameters: vec![],
+            cookie_parameters: vec![],
             asserts: vec![],
             request_body_parameter: "".to_string(),
+            request_body_variant: None,
+            response_body_variant: None,
         };
         let out2 = Output {
             name: "updatePet_200.hurl".to_string(),
@@ -1683,7 +3323,7 @@ mod tests {
             out4.clone(),
             out5.clone(),
         ];
-        let regexes = vec![
+        let regexes = [
             regex_lite::Regex::new("Pets").unwrap(),
             regex_lite::Regex::new("^findPets").unwrap(),
             regex_lite::Regex::new("ById$").unwrap(),
@@ -0,0 +1,202 @@
+use serde_json::{json, Value};
+
+/// Returns true when the document declares itself as an OpenAPI 3.1 spec.
+pub fn is_openapi_3_1(document: &Value) -> bool {
+    document
+        .get("openapi")
+        .and_then(|v| v.as_str())
+        .is_some_and(|v| v.starts_with("3.1"))
+}
+
+/// Rewrites an OpenAPI 3.1 document into the equivalent OpenAPI 3.0 document so that it can be
+/// parsed like any other spec.
+///
+/// Most of the differences are in schemas, which are full JSON Schema in 3.1:
+/// - `type: [string, "null"]` becomes `type: string` with `nullable: true`. Multiple non-null
+///   types become an `anyOf` with one branch per type.
+/// - `const` becomes a single value `enum`.
+/// - `examples` becomes `example` using the first example.
+/// - Numeric `exclusiveMinimum`/`exclusiveMaximum` become `minimum`/`maximum` with the boolean
+///   form of the exclusive keyword.
+/// - `prefixItems` becomes `items` with an `anyOf` of the tuple's schemas.
+/// - Branches of `oneOf`/`anyOf` whose only type is `"null"` are removed and make the schema
+///   `nullable`, whatever annotations (e.g. `description`) they have.
+/// - `$ref` with sibling keywords becomes an `allOf` containing the reference.
+///
+/// `webhooks` and `jsonSchemaDialect` have no 3.0 equivalent and are removed.
+pub fn downgrade(document: &mut Value) {
+    let object = document.as_object_mut();
+    if object.is_none() {
+        return;
+    }
+    let object = object.unwrap();
+    object.insert("openapi".to_string(), json!("3.0.3"));
+    object.remove("webhooks");
+    object.remove("jsonSchemaDialect");
+    // `paths` is optional in 3.1 but required in 3.0
+    object.entry("paths").or_insert_with(|| json!({}));
+    if let Some(components) = object.get_mut("components").and_then(|c| c.as_object_mut()) {
+        components.remove("pathItems");
+    }
+    downgrade_schemas_in(document, false);
+}
+
+/// Finds the schemas in a part of the document and downgrades them.
+fn downgrade_schemas_in(value: &mut Value, is_components: bool) {
+    match value {
+        Value::Object(object) => {
+            for (key, inner) in object.iter_mut() {
+                if key == "schema" {
+                    downgrade_schema(inner);
+                } else if key == "schemas" && is_components {
+                    if let Some(schemas) = inner.as_object_mut() {
+                        schemas.values_mut().for_each(downgrade_schema);
+                    }
+                } else {
+                    downgrade_schemas_in(inner, key == "components");
+                }
+            }
+        }
+        Value::Array(array) => {
+            array
+                .iter_mut()
+                .for_each(|inner| downgrade_schemas_in(inner, false));
+        }
+        _ => {}
+    }
+}
+
+fn downgrade_schema(schema: &mut Value) {
+    let object = schema.as_object_mut();
+    if object.is_none() {
+        return;
+    }
+    let object = object.unwrap();
+
+    // Null branches are removed before the branches are downgraded, which would leave them as
+    // schemas without a type.
+    let mut is_nullable = false;
+    for key in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(branches)) = object.get_mut(key) {
+            let length = branches.len();
+            branches.retain(|branch| !is_null_schema(branch));
+            is_nullable |= branches.len() < length;
+        }
+    }
+
+    // Nested schemas are downgraded first so the checks below see 3.0 schemas.
+    for key in ["items", "not", "additionalProperties"] {
+        if let Some(inner) = object.get_mut(key) {
+            downgrade_schema(inner);
+        }
+    }
+    for key in ["allOf", "oneOf", "anyOf", "prefixItems"] {
+        if let Some(Value::Array(inner)) = object.get_mut(key) {
+            inner.iter_mut().for_each(downgrade_schema);
+        }
+    }
+    for key in ["properties", "$defs"] {
+        if let Some(Value::Object(inner)) = object.get_mut(key) {
+            inner.values_mut().for_each(downgrade_schema);
+        }
+    }
+
+    match object.remove("type") {
+        Some(Value::Array(types)) => {
+            is_nullable |= types.iter().any(|t| t == "null");
+            let mut types: Vec<Value> = types.into_iter().filter(|t| t != "null").collect();
+            match types.len() {
+                0 => {}
+                1 => {
+                    object.insert("type".to_string(), types.pop().unwrap());
+                }
+                _ => {
+                    let branches: Vec<Value> =
+                        types.into_iter().map(|t| json!({ "type": t })).collect();
+                    match object.remove("anyOf") {
+                        // The value has to match one of the types and one of the existing
+                        // branches.
+                        Some(any_of) => {
+                            let all_of = object
+                                .entry("allOf")
+                                .or_insert_with(|| Value::Array(vec![]));
+                            if let Value::Array(all_of) = all_of {
+                                all_of.push(json!({ "anyOf": any_of }));
+                                all_of.push(json!({ "anyOf": branches }));
+                            }
+                        }
+                        None => {
+                            object.insert("anyOf".to_string(), Value::Array(branches));
+                        }
+                    }
+                }
+            }
+        }
+        Some(Value::String(t)) if t == "null" => is_nullable = true,
+        Some(t) => {
+            object.insert("type".to_string(), t);
+        }
+        None => {}
+    }
+
+    if let Some(value) = object.remove("const") {
+        object
+            .entry("enum")
+            .or_insert_with(|| Value::Array(vec![value]));
+    }
+
+    if let Some(Value::Array(examples)) = object.remove("examples") {
+        if let Some(example) = examples.into_iter().next() {
+            object.entry("example").or_insert(example);
+        }
+    }
+
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        if let Some(value) = object.get(exclusive).filter(|v| v.is_number()).cloned() {
+            object.insert(bound.to_string(), value);
+            object.insert(exclusive.to_string(), json!(true));
+        }
+    }
+
+    if let Some(Value::Array(mut branches)) = object.remove("prefixItems") {
+        // `items` describes every element after the tuple in 3.1, or is `false` when there
+        // can't be any.
+        if let Some(items) = object.remove("items").filter(|i| i.is_object()) {
+            branches.push(items);
+        }
+        let items = match branches.len() {
+            1 => branches.pop().unwrap(),
+            _ => json!({ "anyOf": branches }),
+        };
+        object.insert("items".to_string(), items);
+    }
+    if object.get("items").is_some_and(|i| !i.is_object()) {
+        object.remove("items");
+    }
+
+    if is_nullable {
+        object.insert("nullable".to_string(), json!(true));
+    }
+
+    if object.contains_key("$ref") && object.len() > 1 {
+        let reference = object.remove("$ref").unwrap();
+        let all_of = object
+            .entry("allOf")
+            .or_insert_with(|| Value::Array(vec![]));
+        if let Value::Array(all_of) = all_of {
+            all_of.insert(0, json!({ "$ref": reference }));
+        }
+    }
+}
+
+/// Whether a 3.1 schema only allows `null`, e.g. `{type: "null", description: No owner}`.
+fn is_null_schema(schema: &Value) -> bool {
+    match schema.get("type") {
+        Some(Value::String(t)) => t == "null",
+        Some(Value::Array(types)) => !types.is_empty() && types.iter().all(|t| t == "null"),
+        _ => false,
+    }
+}
@@ -0,0 +1,119 @@
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use crate::{input::InputSource, parse, HeaveError, InputSpecFormat};
+
+/// An [OpenAPI Overlay 1.0](https://spec.openapis.org/overlay/v1.0.0.html) document, which
+/// changes a spec without editing it.
+#[derive(Debug, Deserialize)]
+pub struct Overlay {
+    /// Where the overlay was read from, used when reporting actions that didn't match.
+    #[serde(skip)]
+    location: String,
+    actions: Vec<Action>,
+}
+
+/// Changes every node in a spec that `target` selects.
+#[derive(Debug, Deserialize)]
+struct Action {
+    target: JsonPath,
+    #[serde(default)]
+    update: Option<Value>,
+    #[serde(default)]
+    remove: bool,
+}
+
+impl Overlay {
+    /// Parses the content of the overlay at `location`, which may be JSON or YAML.
+    pub fn load(content: &str, location: &InputSource) -> Result<Self, HeaveError> {
+        let file = location.to_string();
+        let format = InputSpecFormat::sniff(content);
+        let mut overlay: Overlay = parse::parse_content(content, &format)
+            .map_err(|e| e.locate(content, &format).into_error(&file))?;
+        overlay.location = file;
+        Ok(overlay)
+    }
+
+    /// Applies each action to the document in order. Objects selected by a target have `update`
+    /// merged into them and arrays have `update` appended to them. Targets that don't select
+    /// anything are reported as diagnostics.
+    pub fn apply(&self, document: &mut Value) -> Vec<HeaveError> {
+        let mut diagnostics = vec![];
+        for action in self.actions.iter() {
+            let pointers: Vec<String> = action
+                .target
+                .query_located(document)
+                .locations()
+                .map(|location| location.to_json_pointer())
+                .collect();
+            if pointers.is_empty() {
+                diagnostics.push(HeaveError::UnmatchedOverlayTarget {
+                    file: self.location.clone(),
+                    target: action.target.to_string(),
+                });
+                continue;
+            }
+
+            if action.remove {
+                // Removing the last nodes first keeps the indexes of earlier array elements valid
+                for pointer in pointers.iter().rev() {
+                    remove_pointer(document, pointer);
+                }
+                continue;
+            }
+            if action.update.is_none() {
+                continue;
+            }
+            let update = action.update.as_ref().unwrap();
+            for pointer in pointers.iter() {
+                match document.pointer_mut(pointer) {
+                    Some(Value::Array(array)) => array.push(update.clone()),
+                    Some(node) => merge(node, update),
+                    None => {}
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Merges `update` into `node`. Properties of objects are merged recursively and any other value
+/// replaces the one in `node`.
+fn merge(node: &mut Value, update: &Value) {
+    match (node, update) {
+        (Value::Object(node), Value::Object(update)) => {
+            for (key, value) in update.iter() {
+                match node.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        node.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (node, update) => *node = update.clone(),
+    }
+}
+
+/// Removes the node at `pointer` from the object or array that contains it. The root of the
+/// document can't be removed.
+fn remove_pointer(document: &mut Value, pointer: &str) {
+    let split = pointer.rsplit_once('/');
+    if split.is_none() {
+        return;
+    }
+    let (parent, token) = split.unwrap();
+    let token = token.replace("~1", "/").replace("~0", "~");
+    match document.pointer_mut(parent) {
+        Some(Value::Object(object)) => {
+            object.remove(&token);
+        }
+        Some(Value::Array(array)) => {
+            if let Some(index) = token.parse::<usize>().ok().filter(|i| *i < array.len()) {
+                array.remove(index);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use openapiv3::OpenAPI;
+use serde::de::{DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+
+use crate::{HeaveError, InputSpecFormat};
+
+/// A spec that couldn't be parsed, along with where the problem was found.
+#[derive(Debug)]
+pub struct ParseFailure {
+    line: Option<usize>,
+    column: Option<usize>,
+    path: Vec<PathSegment>,
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    Unknown,
+}
+
+impl ParseFailure {
+    fn from_path_error<E: fmt::Display>(error: serde_path_to_error::Error<E>) -> Self {
+        let path = error
+            .path()
+            .iter()
+            .filter_map(|segment| match segment {
+                serde_path_to_error::Segment::Seq { index } => Some(PathSegment::Index(*index)),
+                serde_path_to_error::Segment::Map { key } => Some(PathSegment::Key(key.clone())),
+                serde_path_to_error::Segment::Enum { .. } => None,
+                serde_path_to_error::Segment::Unknown => Some(PathSegment::Unknown),
+            })
+            .collect();
+        let message = error.into_inner().to_string();
+        // The error messages end with the location, which is reported separately
+        let message = match message.split_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => message,
+        };
+        ParseFailure {
+            line: None,
+            column: None,
+            path,
+            message,
+        }
+    }
+
+    /// The path of the node that couldn't be parsed, e.g. `paths./pets.get.responses.200`.
+    fn jsonpath(&self) -> String {
+        if self.path.is_empty() {
+            return ".".to_string();
+        }
+        let mut jsonpath = String::new();
+        for segment in self.path.iter() {
+            match segment {
+                PathSegment::Index(index) => jsonpath.push_str(&format!("[{}]", index)),
+                PathSegment::Key(key) if jsonpath.is_empty() => jsonpath.push_str(key),
+                PathSegment::Key(key) => jsonpath.push_str(&format!(".{}", key)),
+                PathSegment::Unknown => jsonpath.push_str(".?"),
+            }
+        }
+        jsonpath
+    }
+
+    /// Finds the line and column of the node that couldn't be parsed in the content it came
+    /// from.
+    pub fn locate(mut self, content: &str, format: &InputSpecFormat) -> Self {
+        if self.line.is_some() || self.path.contains(&PathSegment::Unknown) {
+            return self;
+        }
+        let seed = Locate { path: &self.path };
+        // Locating always fails, the error is where the location comes from.
+        let (line, column) = match format {
+            InputSpecFormat::Json => {
+                let mut deserializer = serde_json::Deserializer::from_str(content);
+                match seed.deserialize(&mut deserializer) {
+                    Err(e) => (Some(e.line()), Some(e.column())),
+                    Ok(_) => (None, None),
+                }
+            }
+            InputSpecFormat::Yaml => {
+                let deserializer = serde_yaml::Deserializer::from_str(content);
+                match seed
+                    .deserialize(deserializer)
+                    .err()
+                    .and_then(|e| e.location())
+                {
+                    Some(location) => (Some(location.line()), Some(location.column())),
+                    None => (None, None),
+                }
+            }
+        };
+        self.line = line;
+        self.column = column;
+        self
+    }
+
+    pub fn into_error(self, file: &str) -> HeaveError {
+        HeaveError::SpecParseFailure {
+            file: file.to_string(),
+            line: self.line,
+            column: self.column,
+            jsonpath: self.jsonpath(),
+            message: self.message,
+        }
+    }
+}
+
+impl fmt::Display for ParseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.jsonpath())
+    }
+}
+
+impl std::error::Error for ParseFailure {}
+
+/// Parses the content of a spec, keeping track of the JSON path being parsed so that it can be
+/// reported if parsing fails.
+pub fn parse_content<T: DeserializeOwned>(
+    content: &str,
+    format: &InputSpecFormat,
+) -> Result<T, ParseFailure> {
+    match format {
+        InputSpecFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(content);
+            serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+                let (line, column) = (e.inner().line(), e.inner().column());
+                ParseFailure {
+                    line: Some(line),
+                    column: Some(column),
+                    ..ParseFailure::from_path_error(e)
+                }
+            })
+        }
+        InputSpecFormat::Yaml => {
+            let deserializer = serde_yaml::Deserializer::from_str(content);
+            serde_path_to_error::deserialize(deserializer).map_err(|e| {
+                let location = e.inner().location();
+                ParseFailure {
+                    line: location.as_ref().map(|l| l.line()),
+                    column: location.as_ref().map(|l| l.column()),
+                    ..ParseFailure::from_path_error(e)
+                }
+            })
+        }
+    }
+}
+
+/// Parses an OpenAPI spec from its document.
+///
+/// References and schemas are untagged enums, which means that when they fail to parse the
+/// error only says that nothing matched at the reference, not what was wrong inside of it. To
+/// get to the actual problem, the node that failed is parsed again as the type its position in
+/// the spec says it should be, until the error can't be narrowed down any further.
+pub fn parse_openapi(document: &Value) -> Result<OpenAPI, ParseFailure> {
+    let mut failure = match serde_path_to_error::deserialize::<_, OpenAPI>(document) {
+        Ok(openapi) => return Ok(openapi),
+        Err(e) => ParseFailure::from_path_error(e),
+    };
+    loop {
+        let node = find_node(document, &failure.path);
+        if node.is_none() {
+            return Err(failure);
+        }
+        let node = node.unwrap();
+        if let Some(narrowed) = parse_node(node, &failure.path) {
+            failure.message = narrowed.message;
+            if !narrowed.path.is_empty() {
+                failure.path.extend(narrowed.path);
+                continue;
+            }
+        }
+        // Flattened maps (like `paths` and `responses`) don't say which of their entries failed,
+        // so look for an entry that fails on its own.
+        let failing_child = children(node).find_map(|(segment, child)| {
+            let mut child_path = failure.path.clone();
+            child_path.push(segment);
+            parse_node(child, &child_path).map(|narrowed| (child_path, narrowed))
+        });
+        if failing_child.is_none() {
+            return Err(failure);
+        }
+        let (child_path, narrowed) = failing_child.unwrap();
+        failure.path = child_path;
+        failure.path.extend(narrowed.path);
+        failure.message = narrowed.message;
+    }
+}
+
+/// Parses a node as the kind its position implies, returning why it failed relative to the
+/// node. References aren't parsed since parsing them as an item wouldn't explain anything.
+fn parse_node(node: &Value, path: &[PathSegment]) -> Option<ParseFailure> {
+    if node.get("$ref").is_some() {
+        return None;
+    }
+    node_kind(path)?.parse(node)
+}
+
+fn children(node: &Value) -> Box<dyn Iterator<Item = (PathSegment, &Value)> + '_> {
+    match node {
+        Value::Object(object) => Box::new(
+            object
+                .iter()
+                .map(|(key, child)| (PathSegment::Key(key.clone()), child)),
+        ),
+        Value::Array(array) => Box::new(
+            array
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (PathSegment::Index(index), child)),
+        ),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// The types in a spec that can either be a reference or the item itself.
+#[derive(Clone, Copy)]
+enum NodeKind {
+    PathItem,
+    Parameter,
+    RequestBody,
+    Response,
+    Schema,
+    Header,
+    Example,
+    Link,
+}
+
+impl NodeKind {
+    /// Parses a node as this kind, returning why it failed relative to the node.
+    fn parse(self, node: &Value) -> Option<ParseFailure> {
+        fn failure<T: DeserializeOwned>(node: &Value) -> Option<ParseFailure> {
+            serde_path_to_error::deserialize::<_, T>(node)
+                .err()
+                .map(ParseFailure::from_path_error)
+        }
+        match self {
+            NodeKind::PathItem => failure::<openapiv3::PathItem>(node),
+            NodeKind::Parameter => failure::<openapiv3::Parameter>(node),
+            NodeKind::RequestBody => failure::<openapiv3::RequestBody>(node),
+            NodeKind::Response => failure::<openapiv3::Response>(node),
+            NodeKind::Schema => failure::<openapiv3::Schema>(node),
+            NodeKind::Header => failure::<openapiv3::Header>(node),
+            NodeKind::Example => failure::<openapiv3::Example>(node),
+            NodeKind::Link => failure::<openapiv3::Link>(node),
+        }
+    }
+}
+
+/// Works out the kind of node at a path based on the keys leading up to it.
+fn node_kind(path: &[PathSegment]) -> Option<NodeKind> {
+    let last = path.last()?;
+    let parent = match path.len() {
+        0 | 1 => None,
+        length => match &path[length - 2] {
+            PathSegment::Key(key) => Some(key.as_str()),
+            _ => None,
+        },
+    };
+    let kind = match (parent, last) {
+        (Some("properties"), PathSegment::Key(_)) | (Some("schemas"), PathSegment::Key(_)) => {
+            NodeKind::Schema
+        }
+        (Some("allOf" | "oneOf" | "anyOf"), PathSegment::Index(_)) => NodeKind::Schema,
+        (_, PathSegment::Key(key))
+            if matches!(
+                key.as_str(),
+                "schema" | "items" | "not" | "additionalProperties"
+            ) =>
+        {
+            NodeKind::Schema
+        }
+        (Some("paths"), PathSegment::Key(_)) if path.len() == 2 => NodeKind::PathItem,
+        (Some("parameters"), _) => NodeKind::Parameter,
+        (_, PathSegment::Key(key)) if key == "requestBody" => NodeKind::RequestBody,
+        (Some("requestBodies"), PathSegment::Key(_)) => NodeKind::RequestBody,
+        (Some("responses"), PathSegment::Key(_)) => NodeKind::Response,
+        (Some("headers"), PathSegment::Key(_)) => NodeKind::Header,
+        (Some("examples"), PathSegment::Key(_)) => NodeKind::Example,
+        (Some("links"), PathSegment::Key(_)) => NodeKind::Link,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn find_node<'a>(document: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(document, |node, segment| match segment {
+            PathSegment::Key(key) => node.get(key),
+            PathSegment::Index(index) => node.get(index),
+            PathSegment::Unknown => None,
+        })
+}
+
+/// Walks the content of a spec down to the node at `path` and fails there, so that the error
+/// carries the line and column of that node.
+struct Locate<'a> {
+    path: &'a [PathSegment],
+}
+
+impl<'de> DeserializeSeed<'de> for Locate<'_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Locate<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the node that failed to parse")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let (first, rest) = self.path.split_first().ok_or_else(found)?;
+        while let Some(key) = map.next_key::<Value>()? {
+            // YAML allows keys that aren't strings, like `200`
+            let key = match key {
+                Value::String(key) => key,
+                other => other.to_string(),
+            };
+            if matches!(first, PathSegment::Key(k) if *k == key) {
+                return map.next_value_seed(Locate { path: rest });
+            }
+            map.next_value::<IgnoredAny>()?;
+        }
+        Err(found())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let (first, rest) = self.path.split_first().ok_or_else(found)?;
+        let mut index = 0;
+        loop {
+            if matches!(first, PathSegment::Index(i) if *i == index) {
+                return seq
+                    .next_element_seed(Locate { path: rest })?
+                    .ok_or_else(found);
+            }
+            if seq.next_element::<IgnoredAny>()?.is_none() {
+                return Err(found());
+            }
+            index += 1;
+        }
+    }
+}
+
+fn found<E: serde::de::Error>() -> E {
+    E::custom("found the node that failed to parse")
+}
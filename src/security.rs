@@ -0,0 +1,299 @@
+use std::borrow::Cow;
+
+use openapiv3::{APIKeyLocation, SecurityScheme};
+use serde::Serialize;
+
+use crate::{
+    resolve_reference, spec::Spec, variables::Variable, DiagnosticContext, HeaveError, Output,
+};
+
+/// The value that replaces credentials in the `_bad_token` files.
+const INVALID_CREDENTIAL: &str = "invalid";
+
+/// How a request is authenticated, which is available to the template as `authentication`.
+///
+/// Credential values are hurl text that reference a variable named after the security scheme,
+/// e.g. `Bearer {{ petstore_auth }}`. Operations that don't require authentication have an empty
+/// `Authentication`.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Authentication {
+    pub headers: Vec<Credential>,
+    pub query_parameters: Vec<Credential>,
+    pub cookies: Vec<Credential>,
+    /// The username is in `name` and the password in `value`.
+    pub basic_auth: Option<Credential>,
+    /// Requests that have to be sent first to get an access token.
+    pub token_requests: Vec<TokenRequest>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Credential {
+    pub name: String,
+    pub value: String,
+}
+
+impl Authentication {
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+            && self.query_parameters.is_empty()
+            && self.cookies.is_empty()
+            && self.basic_auth.is_none()
+            && self.token_requests.is_empty()
+    }
+
+    /// The same credentials, sent with an invalid value instead of their variables. No token is
+    /// requested since the token is replaced anyway.
+    fn with_invalid_credentials(&self) -> Self {
+        // Credential values are made of text and variables, e.g. `Bearer {{ petstore_auth }}`
+        let variable = regex_lite::Regex::new(r"\{\{[^}]*\}\}").unwrap();
+        let invalidate = |credentials: &Vec<Credential>| -> Vec<Credential> {
+            credentials
+                .iter()
+                .map(|credential| Credential {
+                    name: credential.name.clone(),
+                    value: variable
+                        .replace_all(&credential.value, INVALID_CREDENTIAL)
+                        .to_string(),
+                })
+                .collect()
+        };
+        Authentication {
+            headers: invalidate(&self.headers),
+            query_parameters: invalidate(&self.query_parameters),
+            cookies: invalidate(&self.cookies),
+            basic_auth: self.basic_auth.as_ref().map(|_| Credential {
+                name: INVALID_CREDENTIAL.to_string(),
+                value: INVALID_CREDENTIAL.to_string(),
+            }),
+            token_requests: vec![],
+        }
+    }
+}
+
+/// A request for an OAuth2 access token using the client credentials flow. The token is captured
+/// into the variable that the `Authorization` header of the operation's request uses.
+#[derive(Clone, Debug, Serialize)]
+pub struct TokenRequest {
+    pub url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// The scopes the operation requires, separated by spaces.
+    pub scope: Option<String>,
+    pub capture: String,
+}
+
+/// Works out how an operation is authenticated. The operation's `security` overrides the spec's,
+/// and when there are alternatives the first one is used. Every scheme of that alternative is
+/// applied to the request.
+///
+/// OAuth2 schemes with a client credentials flow get a token request, which is sent to the
+/// `tokenUrl` before the operation's request. Relative token URLs are resolved against
+/// `base_url_variable`.
+pub fn authentication(
+    spec: &Spec,
+    operation: &openapiv3::Operation,
+    base_url_variable: &str,
+    diagnostic_context: &DiagnosticContext,
+) -> (Authentication, Vec<HeaveError>) {
+    let mut authentication = Authentication::default();
+    let (schemes, diagnostics) = resolve_schemes(spec, operation, diagnostic_context);
+    for (name, scheme, scopes) in schemes.iter() {
+        let variable = variable_name(name);
+        match scheme.as_ref() {
+            SecurityScheme::APIKey {
+                location,
+                name: key_name,
+                ..
+            } => {
+                let credential = Credential {
+                    name: key_name.clone(),
+                    value: hurl_variable(&variable),
+                };
+                match location {
+                    APIKeyLocation::Header => authentication.headers.push(credential),
+                    APIKeyLocation::Query => authentication.query_parameters.push(credential),
+                    APIKeyLocation::Cookie => authentication.cookies.push(credential),
+                }
+            }
+            SecurityScheme::HTTP { scheme, .. } if scheme.eq_ignore_ascii_case("basic") => {
+                authentication.basic_auth = Some(Credential {
+                    name: hurl_variable(&format!("{}_username", variable)),
+                    value: hurl_variable(&format!("{}_password", variable)),
+                });
+            }
+            SecurityScheme::HTTP { scheme, .. } => {
+                let scheme = match scheme.eq_ignore_ascii_case("bearer") {
+                    true => "Bearer",
+                    false => scheme,
+                };
+                authentication.headers.push(Credential {
+                    name: "Authorization".to_string(),
+                    value: format!("{} {}", scheme, hurl_variable(&variable)),
+                });
+            }
+            SecurityScheme::OAuth2 { flows, .. } => {
+                if let Some(token_url) = client_credentials_token_url(flows) {
+                    let url = match token_url.contains("://") {
+                        true => token_url,
+                        false => format!(
+                            "{}/{}",
+                            hurl_variable(base_url_variable),
+                            token_url.trim_start_matches('/')
+                        ),
+                    };
+                    authentication.token_requests.push(TokenRequest {
+                        url,
+                        client_id: hurl_variable(&format!("{}_client_id", variable)),
+                        client_secret: hurl_variable(&format!("{}_client_secret", variable)),
+                        scope: (!scopes.is_empty()).then(|| scopes.join(" ")),
+                        capture: variable.clone(),
+                    });
+                }
+                authentication.headers.push(Credential {
+                    name: "Authorization".to_string(),
+                    value: format!("Bearer {}", hurl_variable(&variable)),
+                });
+            }
+            SecurityScheme::OpenIDConnect { .. } => {
+                authentication.headers.push(Credential {
+                    name: "Authorization".to_string(),
+                    value: format!("Bearer {}", hurl_variable(&variable)),
+                });
+            }
+        }
+    }
+    (authentication, diagnostics)
+}
+
+/// The variables that hold the credentials of an operation, as used by [`authentication`].
+pub fn variables(
+    spec: &Spec,
+    operation: &openapiv3::Operation,
+    diagnostic_context: &DiagnosticContext,
+) -> Vec<Variable> {
+    let mut variables = vec![];
+    // Problems with the schemes were already reported when generating
+    let (schemes, _) = resolve_schemes(spec, operation, diagnostic_context);
+    for (name, scheme, _) in schemes.iter() {
+        let variable = variable_name(name);
+        let credentials = match scheme.as_ref() {
+            SecurityScheme::APIKey { .. } => vec![(variable, "The API key")],
+            SecurityScheme::HTTP { scheme, .. } if scheme.eq_ignore_ascii_case("basic") => vec![
+                (format!("{}_username", variable), "The username"),
+                (format!("{}_password", variable), "The password"),
+            ],
+            SecurityScheme::HTTP { scheme, .. } if !scheme.eq_ignore_ascii_case("bearer") => {
+                vec![(variable, "The credentials")]
+            }
+            // The token is captured from the token request
+            SecurityScheme::OAuth2 { flows, .. }
+                if client_credentials_token_url(flows).is_some() =>
+            {
+                vec![
+                    (format!("{}_client_id", variable), "The client ID"),
+                    (format!("{}_client_secret", variable), "The client secret"),
+                ]
+            }
+            _ => vec![(variable, "The bearer token")],
+        };
+        for (variable, description) in credentials {
+            variables.push(Variable {
+                name: variable,
+                value: String::new(),
+                comment: format!("{} for the {} security scheme", description, name),
+            });
+        }
+    }
+    variables
+}
+
+/// A security scheme's name, its definition and the scopes an operation requires.
+type RequiredScheme<'a> = (&'a String, Cow<'a, SecurityScheme>, &'a Vec<String>);
+
+/// Finds the security schemes of the first security requirement that applies to an operation.
+fn resolve_schemes<'a>(
+    spec: &'a Spec,
+    operation: &'a openapiv3::Operation,
+    diagnostic_context: &DiagnosticContext,
+) -> (Vec<RequiredScheme<'a>>, Vec<HeaveError>) {
+    let mut diagnostics = vec![];
+    let mut schemes = vec![];
+    let requirement = operation
+        .security
+        .as_ref()
+        .or(spec.openapi.security.as_ref())
+        .and_then(|requirements| requirements.first());
+    if requirement.is_none() {
+        return (schemes, diagnostics);
+    }
+    let requirement = requirement.unwrap();
+    let components = spec.openapi.components.as_ref();
+    for (name, scopes) in requirement.iter() {
+        let scheme = components.and_then(|c| c.security_schemes.get(name));
+        if scheme.is_none() {
+            diagnostics.push(HeaveError::MissingSecurityScheme {
+                context: diagnostic_context.clone(),
+                scheme: name.clone(),
+            });
+            continue;
+        }
+        let (scheme, mut inner_diagnostics) =
+            resolve_reference(spec, scheme.unwrap(), diagnostic_context);
+        diagnostics.append(&mut inner_diagnostics);
+        if let Some(scheme) = scheme {
+            schemes.push((name, scheme, scopes));
+        }
+    }
+    (schemes, diagnostics)
+}
+
+/// The `tokenUrl` of a client credentials flow. The fields of the flow aren't public in
+/// `openapiv3`, so it is read from the serialized flow instead.
+fn client_credentials_token_url(flows: &openapiv3::OAuth2Flows) -> Option<String> {
+    let flow = serde_json::to_value(flows.client_credentials.as_ref()?).ok()?;
+    flow.get("tokenUrl")?.as_str().map(|url| url.to_string())
+}
+
+/// Scheme names can contain characters that aren't allowed in hurl variable names, which are
+/// replaced with `_`.
+fn variable_name(scheme_name: &str) -> String {
+    scheme_name.replace(
+        |c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+        "_",
+    )
+}
+
+fn hurl_variable(name: &str) -> String {
+    format!("{{{{ {} }}}}", name)
+}
+
+/// Adds files that check an operation rejects requests without valid credentials. Each 401 and 403
+/// response of an operation that requires authentication gets a `_no_auth` file that leaves the
+/// credentials out and a `_bad_token` file that sends invalid ones, right after the file for that
+/// response.
+pub fn add_negative_outputs(outputs: Vec<Output>) -> Vec<Output> {
+    let mut all_outputs = vec![];
+    for output in outputs.into_iter() {
+        let is_negative =
+            matches!(output.expected_status_code, 401 | 403) && !output.authentication.is_empty();
+        if !is_negative {
+            all_outputs.push(output);
+            continue;
+        }
+        let stem = output.name.trim_end_matches(".hurl").to_string();
+        let no_auth = Output {
+            name: format!("{}_no_auth.hurl", stem),
+            authentication: Authentication::default(),
+            ..output.clone()
+        };
+        let bad_token = Output {
+            name: format!("{}_bad_token.hurl", stem),
+            authentication: output.authentication.with_invalid_credentials(),
+            ..output.clone()
+        };
+        all_outputs.push(output);
+        all_outputs.push(no_auth);
+        all_outputs.push(bad_token);
+    }
+    all_outputs
+}
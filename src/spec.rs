@@ -0,0 +1,318 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use openapiv3::OpenAPI;
+
+use crate::{
+    input::{is_url, InputSource},
+    openapi31,
+    overlay::Overlay,
+    parse::{self, ParseFailure},
+    swagger2, HeaveError, InputSpecFormat,
+};
+
+/// An OpenAPI spec along with everything needed to resolve references that point outside of it.
+pub struct Spec {
+    pub openapi: OpenAPI,
+    /// The spec as plain JSON, which is what JSON pointers that don't point at a component are
+    /// resolved against. It is only built the first time such a pointer is found.
+    document: OnceCell<serde_json::Value>,
+    external_documents: ExternalDocuments,
+    /// Problems found while loading the spec, like parts of a Swagger 2.0 spec that couldn't be
+    /// converted. They are reported along with the diagnostics from generation.
+    pub diagnostics: Vec<HeaveError>,
+}
+
+/// The reasons an external reference could not be followed.
+pub enum ExternalReferenceError {
+    /// The referenced file could not be read or parsed.
+    MissingFile { file: String, cause: String },
+    /// The file was loaded but the JSON pointer doesn't point at anything in it.
+    UnreadablePointer,
+}
+
+impl Spec {
+    pub fn new(openapi: OpenAPI) -> Self {
+        Spec {
+            openapi,
+            document: OnceCell::new(),
+            external_documents: ExternalDocuments::new(None),
+            diagnostics: vec![],
+        }
+    }
+
+    /// Parses the content of the spec at `location`, which may be JSON or YAML, and applies the
+    /// overlays to it in order. Content that can't be parsed is reported with the line, column and
+    /// JSON path of the problem.
+    pub fn load(
+        content: &str,
+        location: &InputSource,
+        overlays: &[Overlay],
+    ) -> Result<Self, HeaveError> {
+        let file = location.to_string();
+        let format = InputSpecFormat::sniff(content);
+        let mut document: serde_json::Value =
+            parse::parse_content(content, &format).map_err(|e| e.into_error(&file))?;
+        let overlay_diagnostics: Vec<HeaveError> = overlays
+            .iter()
+            .flat_map(|overlay| overlay.apply(&mut document))
+            .collect();
+        let is_converted = !overlays.is_empty()
+            || swagger2::is_swagger_2(&document)
+            || openapi31::is_openapi_3_1(&document);
+        match Spec::from_document(document) {
+            Ok(mut spec) => {
+                spec.diagnostics.extend(overlay_diagnostics);
+                Ok(spec.with_location(location))
+            }
+            // Converted (or overlaid) specs don't match their content, so only the JSON path can
+            // be reported for them.
+            Err(e) if !is_converted => Err(e.locate(content, &format).into_error(&file)),
+            Err(e) => Err(e.into_error(&file)),
+        }
+    }
+
+    /// Parses a spec from its JSON (or YAML) document. Swagger 2.0 and OpenAPI 3.1 documents are
+    /// converted into their OpenAPI 3.0 equivalent first.
+    pub fn from_document(mut document: serde_json::Value) -> Result<Self, ParseFailure> {
+        let mut diagnostics = vec![];
+        if swagger2::is_swagger_2(&document) {
+            (document, diagnostics) = swagger2::convert(&document);
+        }
+        if openapi31::is_openapi_3_1(&document) {
+            openapi31::downgrade(&mut document);
+        }
+        let openapi = parse::parse_openapi(&document)?;
+        let mut spec = Spec::new(openapi);
+        spec.diagnostics = diagnostics;
+        // Keep the document that was parsed so that pointers can reach keywords that `OpenAPI`
+        // doesn't model, like `$defs`.
+        let _ = spec.document.set(document);
+        Ok(spec)
+    }
+
+    /// Sets where the root document was read from. Without a location, external references are
+    /// resolved relative to the current directory.
+    pub fn with_location(mut self, location: &InputSource) -> Self {
+        self.external_documents = ExternalDocuments::new(Some(location));
+        self
+    }
+
+    /// Finds the value an external reference (e.g. `./schemas/pet.yaml#/Pet`) points at.
+    pub fn resolve_external(
+        &self,
+        reference: &str,
+    ) -> Result<serde_json::Value, ExternalReferenceError> {
+        self.external_documents.resolve(reference)
+    }
+
+    /// Finds the value that the unescaped tokens of a JSON pointer point at in this spec.
+    pub fn resolve_pointer(&self, tokens: &[String]) -> Option<serde_json::Value> {
+        let document = self.document.get_or_init(|| {
+            serde_json::to_value(&self.openapi).expect("OpenAPI specs always serialize to JSON")
+        });
+        find_pointer(document, tokens).cloned()
+    }
+}
+
+/// The documents that external references point to.
+///
+/// References to other files are resolved relative to the directory (or URL) of the root document.
+/// Each document is only read once and is cached for as long as this struct lives.
+pub struct ExternalDocuments {
+    location: Option<InputSource>,
+    documents: RefCell<HashMap<PathBuf, serde_json::Value>>,
+}
+
+impl ExternalDocuments {
+    pub fn new(location: Option<&InputSource>) -> Self {
+        ExternalDocuments {
+            location: location.cloned(),
+            documents: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the file an external reference points to, relative to the root document.
+    pub fn file_of(reference: &str) -> PathBuf {
+        let (file, _) = reference.split_once('#').unwrap_or((reference, ""));
+        if is_url(file) {
+            return PathBuf::from(file);
+        }
+        normalize_path(Path::new(file))
+    }
+
+    /// Finds the value an external reference (e.g. `./schemas/pet.yaml#/Pet`) points at.
+    ///
+    /// References inside the returned value are rewritten so that they are relative to the root
+    /// document, which means they can be resolved like any other reference.
+    pub fn resolve(&self, reference: &str) -> Result<serde_json::Value, ExternalReferenceError> {
+        let tokens =
+            parse_json_pointer(reference).ok_or(ExternalReferenceError::UnreadablePointer)?;
+        let file = Self::file_of(reference);
+        if !self.documents.borrow().contains_key(&file) {
+            let document = self.load_document(&file)?;
+            self.documents.borrow_mut().insert(file.clone(), document);
+        }
+        let documents = self.documents.borrow();
+        let document = documents.get(&file).unwrap();
+        find_pointer(document, &tokens)
+            .cloned()
+            .ok_or(ExternalReferenceError::UnreadablePointer)
+    }
+
+    fn load_document(&self, file: &Path) -> Result<serde_json::Value, ExternalReferenceError> {
+        let missing_file = |cause: String| ExternalReferenceError::MissingFile {
+            file: file.display().to_string(),
+            cause,
+        };
+        // Stdin has no directory, so it resolves relative to the current directory
+        let content = self
+            .location
+            .as_ref()
+            .unwrap_or(&InputSource::Stdin)
+            .join(file)
+            .and_then(|source| source.read())
+            .map_err(|e| missing_file(e.to_string()))?;
+        // YAML is a superset of JSON so this handles both formats.
+        let mut document = serde_yaml::from_str::<serde_json::Value>(&content)
+            .map_err(|e| missing_file(e.to_string()))?;
+        rebase_references(&mut document, file);
+        Ok(document)
+    }
+}
+
+/// Returns true when a reference points into another document instead of the root document.
+pub fn is_external_reference(reference: &str) -> bool {
+    !reference.starts_with('#')
+}
+
+/// Splits the fragment of a reference (everything after `#`) into the unescaped tokens of an
+/// RFC 6901 JSON pointer. The fragment is percent-decoded first, as required for pointers that are
+/// part of a URI, and then `~1` and `~0` are unescaped to `/` and `~`.
+///
+/// Returns `None` when the fragment is not a valid JSON pointer.
+pub fn parse_json_pointer(reference: &str) -> Option<Vec<String>> {
+    let (_, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+    let pointer = percent_decode(fragment)?;
+    if pointer.is_empty() {
+        return Some(vec![]);
+    }
+    let pointer = pointer.strip_prefix('/')?;
+    pointer
+        .split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    unescaped.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => unescaped.push('~'),
+                    Some('1') => unescaped.push('/'),
+                    _ => return None,
+                }
+            }
+            Some(unescaped)
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut remaining = value.bytes();
+    while let Some(byte) = remaining.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let high = (remaining.next()? as char).to_digit(16)?;
+        let low = (remaining.next()? as char).to_digit(16)?;
+        bytes.push((high * 16 + low) as u8);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Walks the unescaped tokens of a JSON pointer through a document. Array elements are selected
+/// by their index.
+fn find_pointer<'a>(
+    document: &'a serde_json::Value,
+    tokens: &[String],
+) -> Option<&'a serde_json::Value> {
+    tokens
+        .iter()
+        .try_fold(document, |value, token| match value {
+            serde_json::Value::Object(object) => object.get(token),
+            serde_json::Value::Array(array) => {
+                // RFC 6901 doesn't allow leading zeros in array indexes
+                if token.len() > 1 && token.starts_with('0') {
+                    return None;
+                }
+                array.get(token.parse::<usize>().ok()?)
+            }
+            _ => None,
+        })
+}
+
+/// Rewrites every `$ref` in a document loaded from `file` so that it can be resolved from the
+/// root document. Local references (`#/Pet`) are prefixed with the file they were found in and
+/// relative file references are joined with that file's directory.
+fn rebase_references(value: &mut serde_json::Value, file: &Path) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, inner) in object.iter_mut() {
+                match inner {
+                    serde_json::Value::String(reference) if key == "$ref" => {
+                        *reference = rebase_reference(reference, file);
+                    }
+                    _ => rebase_references(inner, file),
+                }
+            }
+        }
+        serde_json::Value::Array(array) => {
+            array
+                .iter_mut()
+                .for_each(|inner| rebase_references(inner, file));
+        }
+        _ => {}
+    }
+}
+
+fn rebase_reference(reference: &str, file: &Path) -> String {
+    let (reference_file, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+    let reference_file = if reference_file.is_empty() {
+        file.to_path_buf()
+    } else if is_url(reference_file) {
+        PathBuf::from(reference_file)
+    } else if is_url(&file.to_string_lossy()) {
+        // Joining paths would collapse the `//` of the URL
+        match url::Url::parse(&file.to_string_lossy()).and_then(|url| url.join(reference_file)) {
+            Ok(url) => PathBuf::from(url.to_string()),
+            Err(_) => PathBuf::from(reference_file),
+        }
+    } else {
+        normalize_path(&file.parent().unwrap_or(Path::new("")).join(reference_file))
+    };
+    format!("{}#{}", reference_file.display(), pointer)
+}
+
+/// Removes `.` and `..` from a path without touching the filesystem so that the same file is
+/// always cached under the same key.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            // `file_name` is `None` when the path is empty or already ends in `..`
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,755 @@
+use serde_json::{json, Map, Value};
+
+use crate::HeaveError;
+
+/// The keywords of a Swagger 2.0 non-body parameter (or header, or items object) that describe
+/// its value. In OpenAPI 3.0 these live in the parameter's `schema`.
+const SCHEMA_KEYWORDS: [&str; 17] = [
+    "type",
+    "format",
+    "items",
+    "default",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "enum",
+    "multipleOf",
+    "x-nullable",
+];
+
+/// Returns true when the document declares itself as a Swagger 2.0 spec.
+pub fn is_swagger_2(document: &Value) -> bool {
+    document
+        .get("swagger")
+        .and_then(|v| v.as_str())
+        .is_some_and(|v| v.starts_with("2."))
+}
+
+/// Converts a Swagger 2.0 document into the equivalent OpenAPI 3.0 document.
+///
+/// - `host`, `basePath` and `schemes` become `servers`.
+/// - `definitions`, `parameters`, `responses` and `securityDefinitions` move into `components`.
+///   Global `in: body` parameters become `components/requestBodies`.
+/// - `in: body` and `in: formData` parameters become the operation's `requestBody`, using the
+///   media types from `consumes`.
+/// - Response schemas are placed under each media type from `produces`.
+/// - Non-body parameters and headers have their type keywords moved into a `schema`.
+///
+/// Parts of the spec that can't be represented in OpenAPI 3.0 are reported as diagnostics.
+pub fn convert(document: &Value) -> (Value, Vec<HeaveError>) {
+    let mut converter = Converter {
+        document,
+        diagnostics: vec![],
+    };
+    let converted = converter.convert_document();
+    (converted, converter.diagnostics)
+}
+
+struct Converter<'a> {
+    document: &'a Value,
+    diagnostics: Vec<HeaveError>,
+}
+
+impl<'a> Converter<'a> {
+    fn convert_document(&mut self) -> Value {
+        let document = self.document;
+        let mut converted = Map::new();
+        converted.insert("openapi".to_string(), json!("3.0.3"));
+        for key in ["info", "security", "tags", "externalDocs"] {
+            if let Some(value) = document.get(key) {
+                converted.insert(key.to_string(), value.clone());
+            }
+        }
+        copy_extensions(document, &mut converted);
+
+        let servers = self.convert_servers();
+        if !servers.is_empty() {
+            converted.insert("servers".to_string(), Value::Array(servers));
+        }
+
+        let consumes = media_types(document.get("consumes"));
+        let produces = media_types(document.get("produces"));
+
+        let mut paths = Map::new();
+        if let Some(Value::Object(swagger_paths)) = document.get("paths") {
+            for (path, path_item) in swagger_paths {
+                let location = format!("/paths/{}", escape(path));
+                let path_item = self.convert_path_item(path_item, &consumes, &produces, &location);
+                paths.insert(path.clone(), path_item);
+            }
+        }
+        converted.insert("paths".to_string(), Value::Object(paths));
+
+        let components = self.convert_components(&consumes, &produces);
+        if !components.is_empty() {
+            converted.insert("components".to_string(), Value::Object(components));
+        }
+
+        let mut converted = Value::Object(converted);
+        rewrite_references(&mut converted);
+        converted
+    }
+
+    fn convert_servers(&mut self) -> Vec<Value> {
+        let document = self.document;
+        let host = document.get("host").and_then(|h| h.as_str());
+        let base_path = document
+            .get("basePath")
+            .and_then(|b| b.as_str())
+            .unwrap_or("");
+        let schemes: Vec<&str> = document
+            .get("schemes")
+            .and_then(|s| s.as_array())
+            .map(|s| s.iter().filter_map(|s| s.as_str()).collect())
+            .unwrap_or_default();
+        match host {
+            // Without a host the servers are relative to wherever the spec is served from
+            None if base_path.is_empty() => vec![],
+            None => vec![json!({ "url": base_path })],
+            Some(host) if schemes.is_empty() => {
+                vec![json!({ "url": format!("//{}{}", host, base_path) })]
+            }
+            Some(host) => schemes
+                .iter()
+                .map(|scheme| json!({ "url": format!("{}://{}{}", scheme, host, base_path) }))
+                .collect(),
+        }
+    }
+
+    fn convert_components(
+        &mut self,
+        consumes: &[String],
+        produces: &[String],
+    ) -> Map<String, Value> {
+        let document = self.document;
+        let mut components = Map::new();
+
+        if let Some(Value::Object(definitions)) = document.get("definitions") {
+            let schemas: Map<String, Value> = definitions
+                .iter()
+                .map(|(name, schema)| (name.clone(), convert_schema(schema)))
+                .collect();
+            components.insert("schemas".to_string(), Value::Object(schemas));
+        }
+
+        if let Some(Value::Object(parameters)) = document.get("parameters") {
+            let mut converted_parameters = Map::new();
+            let mut request_bodies = Map::new();
+            for (name, parameter) in parameters {
+                let location = format!("/parameters/{}", escape(name));
+                match parameter.get("in").and_then(|i| i.as_str()) {
+                    Some("body") => {
+                        request_bodies
+                            .insert(name.clone(), convert_body_parameter(parameter, consumes));
+                    }
+                    // Form parameters are combined into a request body where they are used,
+                    // so there's nothing to add to components.
+                    Some("formData") => {}
+                    _ => {
+                        converted_parameters
+                            .insert(name.clone(), self.convert_parameter(parameter, &location));
+                    }
+                }
+            }
+            if !converted_parameters.is_empty() {
+                components.insert(
+                    "parameters".to_string(),
+                    Value::Object(converted_parameters),
+                );
+            }
+            if !request_bodies.is_empty() {
+                components.insert("requestBodies".to_string(), Value::Object(request_bodies));
+            }
+        }
+
+        if let Some(Value::Object(responses)) = document.get("responses") {
+            let converted_responses: Map<String, Value> = responses
+                .iter()
+                .map(|(name, response)| {
+                    let location = format!("/responses/{}", escape(name));
+                    (
+                        name.clone(),
+                        self.convert_response(response, produces, &location),
+                    )
+                })
+                .collect();
+            components.insert("responses".to_string(), Value::Object(converted_responses));
+        }
+
+        if let Some(Value::Object(security_definitions)) = document.get("securityDefinitions") {
+            let security_schemes: Map<String, Value> = security_definitions
+                .iter()
+                .map(|(name, scheme)| {
+                    let location = format!("/securityDefinitions/{}", escape(name));
+                    (
+                        name.clone(),
+                        self.convert_security_scheme(scheme, &location),
+                    )
+                })
+                .collect();
+            components.insert(
+                "securitySchemes".to_string(),
+                Value::Object(security_schemes),
+            );
+        }
+
+        components
+    }
+
+    fn convert_path_item(
+        &mut self,
+        path_item: &Value,
+        consumes: &[String],
+        produces: &[String],
+        location: &str,
+    ) -> Value {
+        let mut converted = Map::new();
+        copy_extensions(path_item, &mut converted);
+
+        // Body and form parameters on the path item have to become part of every operation's
+        // request body, so they are handed down to the operations.
+        let mut shared_parameters = vec![];
+        let mut body_parameters = vec![];
+        if let Some(Value::Array(parameters)) = path_item.get("parameters") {
+            for (index, parameter) in parameters.iter().enumerate() {
+                let parameter_location = format!("{}/parameters/{}", location, index);
+                if self.is_body_or_form_parameter(parameter) {
+                    body_parameters.push((parameter_location, parameter.clone()));
+                } else {
+                    shared_parameters.push(self.convert_parameter(parameter, &parameter_location));
+                }
+            }
+        }
+        if !shared_parameters.is_empty() {
+            converted.insert("parameters".to_string(), Value::Array(shared_parameters));
+        }
+
+        for method in ["get", "put", "post", "delete", "options", "head", "patch"] {
+            if let Some(operation) = path_item.get(method) {
+                let operation_location = format!("{}/{}", location, method);
+                let operation = self.convert_operation(
+                    operation,
+                    &body_parameters,
+                    consumes,
+                    produces,
+                    &operation_location,
+                );
+                converted.insert(method.to_string(), operation);
+            }
+        }
+        Value::Object(converted)
+    }
+
+    fn convert_operation(
+        &mut self,
+        operation: &Value,
+        path_body_parameters: &[(String, Value)],
+        consumes: &[String],
+        produces: &[String],
+        location: &str,
+    ) -> Value {
+        let mut converted = Map::new();
+        if let Value::Object(operation) = operation {
+            for (key, value) in operation {
+                if !matches!(
+                    key.as_str(),
+                    "parameters" | "responses" | "consumes" | "produces" | "schemes"
+                ) {
+                    converted.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        if operation.get("schemes").is_some() {
+            self.lossy(
+                location,
+                "Operation level `schemes` can't be represented and were dropped.",
+            );
+        }
+
+        let consumes = match operation.get("consumes") {
+            Some(c) => media_types(Some(c)),
+            None => consumes.to_vec(),
+        };
+        let produces = match operation.get("produces") {
+            Some(p) => media_types(Some(p)),
+            None => produces.to_vec(),
+        };
+
+        let mut parameters = vec![];
+        let mut body: Option<Value> = None;
+        let mut form_parameters: Vec<Value> = vec![];
+        let operation_parameters: Vec<Value> = operation
+            .get("parameters")
+            .and_then(|p| p.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let is_overridden = |parameter: &Value| {
+            let resolved = self.resolve_parameter(parameter);
+            operation_parameters.iter().any(|p| {
+                let p = self.resolve_parameter(p);
+                p.get("name") == resolved.get("name") && p.get("in") == resolved.get("in")
+            })
+        };
+        let inherited: Vec<(String, Value)> = path_body_parameters
+            .iter()
+            .filter(|(_, p)| !is_overridden(p))
+            .cloned()
+            .collect();
+        let own = operation_parameters
+            .iter()
+            .enumerate()
+            .map(|(index, p)| (format!("{}/parameters/{}", location, index), p.clone()));
+        for (parameter_location, parameter) in inherited.into_iter().chain(own) {
+            let resolved = self.resolve_parameter(&parameter).clone();
+            match resolved.get("in").and_then(|i| i.as_str()) {
+                Some("body") => {
+                    if body.is_some() {
+                        self.lossy(
+                            &parameter_location,
+                            "Only one body parameter is allowed. This one was dropped.",
+                        );
+                        continue;
+                    }
+                    body = Some(match parameter.get("$ref").and_then(|r| r.as_str()) {
+                        Some(reference) => json!({
+                            "$ref": reference.replacen("#/parameters/", "#/components/requestBodies/", 1)
+                        }),
+                        None => convert_body_parameter(&resolved, &consumes),
+                    });
+                }
+                Some("formData") => form_parameters.push(resolved),
+                _ => match parameter.get("$ref").and_then(|r| r.as_str()) {
+                    Some(reference) => parameters.push(json!({
+                        "$ref": reference.replacen("#/parameters/", "#/components/parameters/", 1)
+                    })),
+                    None => parameters.push(self.convert_parameter(&resolved, &parameter_location)),
+                },
+            }
+        }
+
+        if !parameters.is_empty() {
+            converted.insert("parameters".to_string(), Value::Array(parameters));
+        }
+        if body.is_some() && !form_parameters.is_empty() {
+            self.lossy(
+                location,
+                "Operations can't have both body and formData parameters. The formData parameters were dropped.",
+            );
+        }
+        if let Some(body) = body {
+            converted.insert("requestBody".to_string(), body);
+        } else if !form_parameters.is_empty() {
+            let request_body = self.convert_form_parameters(&form_parameters, &consumes, location);
+            converted.insert("requestBody".to_string(), request_body);
+        }
+
+        let mut responses = Map::new();
+        if let Some(Value::Object(swagger_responses)) = operation.get("responses") {
+            for (status_code, response) in swagger_responses {
+                let response_location = format!("{}/responses/{}", location, escape(status_code));
+                responses.insert(
+                    status_code.clone(),
+                    self.convert_response(response, &produces, &response_location),
+                );
+            }
+        }
+        converted.insert("responses".to_string(), Value::Object(responses));
+        Value::Object(converted)
+    }
+
+    /// Follows a reference to a global parameter. Anything else is returned as is.
+    fn resolve_parameter<'b>(&self, parameter: &'b Value) -> &'b Value
+    where
+        'a: 'b,
+    {
+        let reference = parameter.get("$ref").and_then(|r| r.as_str());
+        reference
+            .and_then(|r| r.strip_prefix("#/parameters/"))
+            .and_then(|name| self.document.get("parameters")?.get(unescape(name)))
+            .unwrap_or(parameter)
+    }
+
+    fn is_body_or_form_parameter(&self, parameter: &Value) -> bool {
+        matches!(
+            self.resolve_parameter(parameter)
+                .get("in")
+                .and_then(|i| i.as_str()),
+            Some("body") | Some("formData")
+        )
+    }
+
+    fn convert_parameter(&mut self, parameter: &Value, location: &str) -> Value {
+        if let Some(reference) = parameter.get("$ref").and_then(|r| r.as_str()) {
+            return json!({
+                "$ref": reference.replacen("#/parameters/", "#/components/parameters/", 1)
+            });
+        }
+        let mut converted = Map::new();
+        let mut schema = Map::new();
+        if let Value::Object(parameter) = parameter {
+            for (key, value) in parameter {
+                if SCHEMA_KEYWORDS.contains(&key.as_str()) {
+                    schema.insert(key.clone(), value.clone());
+                } else if key != "collectionFormat" {
+                    converted.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        if schema.get("type").and_then(|t| t.as_str()) == Some("file") {
+            self.lossy(
+                location,
+                "File parameters are only supported in formData. The parameter was converted to a binary string.",
+            );
+        }
+        let collection_format = parameter.get("collectionFormat").and_then(|c| c.as_str());
+        let is_query = parameter.get("in").and_then(|i| i.as_str()) == Some("query");
+        match collection_format {
+            // The OpenAPI 3.0 defaults match csv for every location except query
+            None | Some("csv") if is_query => {
+                converted.insert("style".to_string(), json!("form"));
+                converted.insert("explode".to_string(), json!(false));
+            }
+            None | Some("csv") => {}
+            Some("multi") => {
+                converted.insert("style".to_string(), json!("form"));
+                converted.insert("explode".to_string(), json!(true));
+            }
+            Some("ssv") => {
+                converted.insert("style".to_string(), json!("spaceDelimited"));
+            }
+            Some("pipes") => {
+                converted.insert("style".to_string(), json!("pipeDelimited"));
+            }
+            Some(format) => self.lossy(
+                location,
+                &format!(
+                    "collectionFormat `{}` has no OpenAPI 3.0 equivalent and was dropped.",
+                    format
+                ),
+            ),
+        }
+        // Collection formats only matter for arrays.
+        if schema.get("type").and_then(|t| t.as_str()) != Some("array") {
+            converted.remove("style");
+            converted.remove("explode");
+        }
+        converted.insert("schema".to_string(), convert_schema(&Value::Object(schema)));
+        Value::Object(converted)
+    }
+
+    fn convert_form_parameters(
+        &mut self,
+        parameters: &[Value],
+        consumes: &[String],
+        location: &str,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = vec![];
+        let mut has_file = false;
+        for parameter in parameters {
+            let name = parameter
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let mut schema = Map::new();
+            for keyword in SCHEMA_KEYWORDS {
+                if let Some(value) = parameter.get(keyword) {
+                    schema.insert(keyword.to_string(), value.clone());
+                }
+            }
+            if let Some(description) = parameter.get("description") {
+                schema.insert("description".to_string(), description.clone());
+            }
+            has_file |= schema.get("type").and_then(|t| t.as_str()) == Some("file");
+            if parameter.get("required").and_then(|r| r.as_bool()) == Some(true) {
+                required.push(json!(name));
+            }
+            if parameter.get("collectionFormat").is_some() {
+                self.lossy(
+                    location,
+                    &format!(
+                        "collectionFormat of formData parameter `{}` was dropped.",
+                        name
+                    ),
+                );
+            }
+            properties.insert(name, convert_schema(&Value::Object(schema)));
+        }
+
+        let mut schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            schema["required"] = Value::Array(required);
+        }
+        let form_media_types: Vec<&String> = consumes
+            .iter()
+            .filter(|c| {
+                c.starts_with("application/x-www-form-urlencoded")
+                    || c.starts_with("multipart/form-data")
+            })
+            .collect();
+        let media_types: Vec<String> = match (form_media_types.is_empty(), has_file) {
+            (false, _) => form_media_types.into_iter().cloned().collect(),
+            (true, true) => vec!["multipart/form-data".to_string()],
+            (true, false) => vec!["application/x-www-form-urlencoded".to_string()],
+        };
+        let content: Map<String, Value> = media_types
+            .into_iter()
+            .map(|media_type| (media_type, json!({ "schema": schema })))
+            .collect();
+        json!({ "content": content })
+    }
+
+    fn convert_response(&mut self, response: &Value, produces: &[String], location: &str) -> Value {
+        if let Some(reference) = response.get("$ref").and_then(|r| r.as_str()) {
+            return json!({
+                "$ref": reference.replacen("#/responses/", "#/components/responses/", 1)
+            });
+        }
+        let mut converted = Map::new();
+        converted.insert(
+            "description".to_string(),
+            response
+                .get("description")
+                .cloned()
+                .unwrap_or_else(|| json!("")),
+        );
+        copy_extensions(response, &mut converted);
+
+        let examples = response.get("examples").and_then(|e| e.as_object());
+        if let Some(schema) = response.get("schema") {
+            let schema = convert_schema(schema);
+            let content: Map<String, Value> = produces
+                .iter()
+                .map(|media_type| {
+                    let mut media_type_object = json!({ "schema": schema });
+                    if let Some(example) = examples.and_then(|e| e.get(media_type)) {
+                        media_type_object["example"] = example.clone();
+                    }
+                    (media_type.clone(), media_type_object)
+                })
+                .collect();
+            converted.insert("content".to_string(), Value::Object(content));
+        }
+        for media_type in examples.iter().flat_map(|e| e.keys()) {
+            if response.get("schema").is_none() || !produces.contains(media_type) {
+                self.lossy(
+                    location,
+                    &format!(
+                        "The example for `{}` doesn't match a produced media type with a schema and was dropped.",
+                        media_type
+                    ),
+                );
+            }
+        }
+
+        if let Some(Value::Object(headers)) = response.get("headers") {
+            let converted_headers: Map<String, Value> = headers
+                .iter()
+                .map(|(name, header)| {
+                    let mut converted_header = Map::new();
+                    let mut schema = Map::new();
+                    if let Value::Object(header) = header {
+                        for (key, value) in header {
+                            if SCHEMA_KEYWORDS.contains(&key.as_str()) {
+                                schema.insert(key.clone(), value.clone());
+                            } else if key != "collectionFormat" {
+                                converted_header.insert(key.clone(), value.clone());
+                            }
+                        }
+                    }
+                    converted_header
+                        .insert("schema".to_string(), convert_schema(&Value::Object(schema)));
+                    (name.clone(), Value::Object(converted_header))
+                })
+                .collect();
+            converted.insert("headers".to_string(), Value::Object(converted_headers));
+        }
+        Value::Object(converted)
+    }
+
+    fn convert_security_scheme(&mut self, scheme: &Value, location: &str) -> Value {
+        let mut converted = Map::new();
+        if let Some(description) = scheme.get("description") {
+            converted.insert("description".to_string(), description.clone());
+        }
+        copy_extensions(scheme, &mut converted);
+        match scheme.get("type").and_then(|t| t.as_str()) {
+            Some("basic") => {
+                converted.insert("type".to_string(), json!("http"));
+                converted.insert("scheme".to_string(), json!("basic"));
+            }
+            Some("apiKey") => {
+                converted.insert("type".to_string(), json!("apiKey"));
+                for key in ["name", "in"] {
+                    if let Some(value) = scheme.get(key) {
+                        converted.insert(key.to_string(), value.clone());
+                    }
+                }
+            }
+            Some("oauth2") => {
+                let flow = match scheme.get("flow").and_then(|f| f.as_str()) {
+                    Some("implicit") => "implicit",
+                    Some("password") => "password",
+                    Some("application") => "clientCredentials",
+                    Some("accessCode") => "authorizationCode",
+                    _ => {
+                        self.lossy(
+                            location,
+                            "Unknown OAuth2 flow. The security scheme was dropped.",
+                        );
+                        return json!({ "type": "oauth2", "flows": {} });
+                    }
+                };
+                let mut converted_flow = Map::new();
+                for key in ["authorizationUrl", "tokenUrl"] {
+                    if let Some(value) = scheme.get(key) {
+                        converted_flow.insert(key.to_string(), value.clone());
+                    }
+                }
+                converted_flow.insert(
+                    "scopes".to_string(),
+                    scheme.get("scopes").cloned().unwrap_or_else(|| json!({})),
+                );
+                converted.insert("type".to_string(), json!("oauth2"));
+                converted.insert("flows".to_string(), json!({ flow: converted_flow }));
+            }
+            _ => {
+                self.lossy(
+                    location,
+                    "Unknown security scheme type. It was copied as is.",
+                );
+                return scheme.clone();
+            }
+        }
+        Value::Object(converted)
+    }
+
+    fn lossy(&mut self, location: &str, message: &str) {
+        self.diagnostics.push(HeaveError::LossySwaggerConversion {
+            location: location.to_string(),
+            message: message.to_string(),
+        });
+    }
+}
+
+fn convert_body_parameter(parameter: &Value, consumes: &[String]) -> Value {
+    let schema = convert_schema(parameter.get("schema").unwrap_or(&json!({})));
+    let content: Map<String, Value> = consumes
+        .iter()
+        .map(|media_type| (media_type.clone(), json!({ "schema": schema })))
+        .collect();
+    let mut request_body = json!({ "content": content });
+    for key in ["description", "required"] {
+        if let Some(value) = parameter.get(key) {
+            request_body[key] = value.clone();
+        }
+    }
+    request_body
+}
+
+/// Converts the few parts of a Swagger 2.0 schema that differ from OpenAPI 3.0.
+fn convert_schema(schema: &Value) -> Value {
+    let mut schema = schema.clone();
+    convert_schema_in_place(&mut schema);
+    schema
+}
+
+fn convert_schema_in_place(schema: &mut Value) {
+    let object = schema.as_object_mut();
+    if object.is_none() {
+        return;
+    }
+    let object = object.unwrap();
+    for key in ["items", "additionalProperties"] {
+        if let Some(inner) = object.get_mut(key) {
+            convert_schema_in_place(inner);
+        }
+    }
+    if let Some(Value::Array(all_of)) = object.get_mut("allOf") {
+        all_of.iter_mut().for_each(convert_schema_in_place);
+    }
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        properties.values_mut().for_each(convert_schema_in_place);
+    }
+    if object.get("type").and_then(|t| t.as_str()) == Some("file") {
+        object.insert("type".to_string(), json!("string"));
+        object.insert("format".to_string(), json!("binary"));
+    }
+    if let Some(nullable) = object.remove("x-nullable") {
+        object.insert("nullable".to_string(), nullable);
+    }
+    if let Some(Value::String(property_name)) = object.remove("discriminator") {
+        object.insert(
+            "discriminator".to_string(),
+            json!({ "propertyName": property_name }),
+        );
+    }
+}
+
+/// Points references to definitions and global responses at their new place in `components`.
+/// References to global parameters are handled while converting parameters because body
+/// parameters move to `requestBodies`.
+fn rewrite_references(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, inner) in object.iter_mut() {
+                match inner {
+                    Value::String(reference) if key == "$ref" => {
+                        for (from, to) in [
+                            ("#/definitions/", "#/components/schemas/"),
+                            ("#/responses/", "#/components/responses/"),
+                        ] {
+                            if reference.starts_with(from) {
+                                *reference = reference.replacen(from, to, 1);
+                            }
+                        }
+                    }
+                    _ => rewrite_references(inner),
+                }
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(rewrite_references),
+        _ => {}
+    }
+}
+
+fn media_types(value: Option<&Value>) -> Vec<String> {
+    let media_types: Vec<String> = value
+        .and_then(|v| v.as_array())
+        .map(|v| {
+            v.iter()
+                .filter_map(|m| m.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    if media_types.is_empty() {
+        return vec!["application/json".to_string()];
+    }
+    media_types
+}
+
+fn copy_extensions(from: &Value, to: &mut Map<String, Value>) {
+    if let Value::Object(from) = from {
+        for (key, value) in from {
+            if key.starts_with("x-") {
+                to.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
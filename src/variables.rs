@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+
+use openapiv3::{ParameterSchemaOrContent, SchemaKind, Server, Type};
+
+use crate::{
+    resolve_parameters, resolve_reference, security, spec::Spec, DiagnosticContext, HeaveError,
+    Output,
+};
+
+/// The name of the hurl variables file that is written alongside the generated files.
+pub const VARIABLES_FILE: &str = "variables.env";
+
+/// The variable that holds the base URL of requests that use the spec's servers.
+pub const BASE_URL_VARIABLE: &str = "baseurl";
+
+/// A variable that the generated files reference, which has to be given a value when running
+/// them with hurl.
+#[derive(Debug)]
+pub struct Variable {
+    pub name: String,
+    /// An example value from the spec, or empty when the spec doesn't have one.
+    pub value: String,
+    pub comment: String,
+}
+
+/// Returns the variable that holds the base URL of an operation's requests. Operations and path
+/// items can override the servers of the spec, in which case they get their own variable.
+pub fn base_url_variable(
+    path: &str,
+    path_item: Option<&openapiv3::PathItem>,
+    operation: &openapiv3::Operation,
+    operation_name: &str,
+) -> String {
+    if !operation.servers.is_empty() {
+        return format!("{}_{}", BASE_URL_VARIABLE, slug(operation_name));
+    }
+    if path_item.is_some_and(|p| !p.servers.is_empty()) {
+        return format!("{}_{}", BASE_URL_VARIABLE, slug(path));
+    }
+    BASE_URL_VARIABLE.to_string()
+}
+
+/// Finds every variable that the outputs reference: the base URLs, the credentials of the
+/// security schemes and one variable per path parameter. Base URLs come from the first server.
+/// Path parameter values come from the parameter's `example`, then from its schema's `example`,
+/// `default` or first `enum` value.
+pub fn collect(spec: &Spec, outputs: &[Output]) -> Vec<Variable> {
+    collect_for_server(spec, outputs, None)
+}
+
+/// Returns a complete set of variables for each of the spec's servers, named after the file
+/// they should be written to (e.g. `env.production.env`). Servers are named after their
+/// description, or their position when they don't have one.
+pub fn environments(spec: &Spec, outputs: &[Output]) -> Vec<(String, Vec<Variable>)> {
+    let mut environments: Vec<(String, Vec<Variable>)> = vec![];
+    for (index, server) in spec.openapi.servers.iter().enumerate() {
+        let mut name = server
+            .description
+            .as_deref()
+            .map(slug)
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("server_{}", index + 1));
+        if environments
+            .iter()
+            .any(|(file, _)| *file == environment_file(&name))
+        {
+            name = format!("{}_{}", name, index + 1);
+        }
+        environments.push((
+            environment_file(&name),
+            collect_for_server(spec, outputs, Some(server)),
+        ));
+    }
+    environments
+}
+
+/// Renders the variables in the format of hurl's `--variables-file`, for writing to `file`.
+pub fn render(file: &str, variables: &[Variable]) -> String {
+    let mut content = format!(
+        "# Pass this file to hurl with `--variables-file {}`\n",
+        file
+    );
+    for variable in variables.iter() {
+        content.push_str(&format!(
+            "\n# {}\n{}={}\n",
+            variable.comment, variable.name, variable.value
+        ));
+    }
+    content
+}
+
+/// Collects the variables for the environment of `server`, which is one of the spec's servers.
+/// Operations that override the servers use the server with the same description, or their
+/// first server when none match.
+fn collect_for_server(spec: &Spec, outputs: &[Output], server: Option<&Server>) -> Vec<Variable> {
+    let server = server.or(spec.openapi.servers.first());
+    let mut variables = vec![Variable {
+        name: BASE_URL_VARIABLE.to_string(),
+        value: server.map_or("http://localhost".to_string(), base_url),
+        comment: "The server every request is sent to".to_string(),
+    }];
+
+    // Keyed by the variable name, with the servers and the operations that use them
+    let mut base_url_variables: BTreeMap<String, (&[Server], Vec<String>)> = BTreeMap::new();
+    // Keyed by the parameter name, with the example value and the operations that use it
+    let mut path_variables: BTreeMap<String, (Option<String>, Vec<String>)> = BTreeMap::new();
+    for output in outputs.iter() {
+        let path_item = spec
+            .openapi
+            .paths
+            .paths
+            .get(&output.oas_path)
+            .and_then(|p| p.as_item());
+        if path_item.is_none() {
+            continue;
+        }
+        let path_item = path_item.unwrap();
+        let operation = path_item
+            .iter()
+            .find(|(method, _)| method.eq_ignore_ascii_case(&output.method));
+        if operation.is_none() {
+            continue;
+        }
+        let (method, operation) = operation.unwrap();
+        let operation_name = operation
+            .operation_id
+            .clone()
+            .unwrap_or_else(|| format!("{}_{}", method, output.oas_path.replace("/", "_")));
+
+        if output.base_url_variable != BASE_URL_VARIABLE {
+            let servers = if operation.servers.is_empty() {
+                &path_item.servers
+            } else {
+                &operation.servers
+            };
+            let (_, operations) = base_url_variables
+                .entry(output.base_url_variable.clone())
+                .or_insert((servers, vec![]));
+            if !operations.contains(&operation_name) {
+                operations.push(operation_name.clone());
+            }
+        }
+
+        let context = DiagnosticContext {
+            path: output.oas_path.clone(),
+            operation: operation_name.clone(),
+        };
+        for variable in security::variables(spec, operation, &context) {
+            if !variables.iter().any(|v| v.name == variable.name) {
+                variables.push(variable);
+            }
+        }
+
+        // Problems with the parameters were already reported when generating
+        let (parameters, _) = resolve_parameters(spec, Some(path_item), operation, &context);
+        for parameter in parameters.iter() {
+            if let openapiv3::Parameter::Path { parameter_data, .. } = parameter.as_ref() {
+                // Parameters can be replaced by their example when generating
+                if !output
+                    .hurl_path
+                    .contains(&format!("{{{{{}}}}}", parameter_data.name))
+                {
+                    continue;
+                }
+                let (value, operations) = path_variables
+                    .entry(parameter_data.name.clone())
+                    .or_default();
+                if value.is_none() {
+                    *value = example_value(spec, parameter_data, &context).0;
+                }
+                if !operations.contains(&operation_name) {
+                    operations.push(operation_name.clone());
+                }
+            }
+        }
+    }
+
+    let description = server.and_then(|s| s.description.as_ref());
+    for (name, (servers, operations)) in base_url_variables {
+        let matching_server = servers
+            .iter()
+            .find(|s| description.is_some() && s.description.as_ref() == description)
+            .or(servers.first());
+        variables.push(Variable {
+            name,
+            value: matching_server.map(base_url).unwrap_or_default(),
+            comment: format!("The server used by {}", operations.join(", ")),
+        });
+    }
+    for (name, (value, operations)) in path_variables {
+        variables.push(Variable {
+            name,
+            value: value.unwrap_or_default(),
+            comment: format!("Used by {}", operations.join(", ")),
+        });
+    }
+    variables
+}
+
+/// The URL of a server with its variables set to their defaults, or to their first allowed value
+/// when there is no default. Servers that are relative to where the spec is hosted are made
+/// relative to `localhost`.
+fn base_url(server: &Server) -> String {
+    let mut url = server.url.clone();
+    for (name, variable) in server.variables.iter().flatten() {
+        let value = match variable.default.is_empty() {
+            true => variable.enumeration.first().unwrap_or(&variable.default),
+            false => &variable.default,
+        };
+        url = url.replace(&format!("{{{}}}", name), value);
+    }
+    if url.starts_with('/') {
+        url = format!("http://localhost{}", url);
+    }
+    url.trim_end_matches('/').to_string()
+}
+
+fn environment_file(name: &str) -> String {
+    format!("env.{}.env", name)
+}
+
+/// Lowercases a name and replaces anything that isn't a letter or digit with `_`, so that it can
+/// be used in variable and file names.
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect();
+    slug.split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The value of a parameter's `example`, then of its schema's `example`, `default` or first
+/// `enum` value. The schema is resolved even when the parameter has an example, so that a broken
+/// reference is always reported.
+pub fn example_value(
+    spec: &Spec,
+    parameter_data: &openapiv3::ParameterData,
+    context: &DiagnosticContext,
+) -> (Option<String>, Vec<HeaveError>) {
+    let (schema, diagnostics) = match &parameter_data.format {
+        ParameterSchemaOrContent::Schema(schema) => resolve_reference(spec, schema, context),
+        ParameterSchemaOrContent::Content(_) => (None, vec![]),
+    };
+    if let Some(example) = &parameter_data.example {
+        return (Some(value_to_string(example)), diagnostics);
+    }
+    if let Some(example) = parameter_data
+        .examples
+        .values()
+        .find_map(|example| example.as_item().and_then(|e| e.value.as_ref()))
+    {
+        return (Some(value_to_string(example)), diagnostics);
+    }
+    if schema.is_none() {
+        return (None, diagnostics);
+    }
+    let schema = schema.unwrap();
+    if let Some(example) = &schema.schema_data.example {
+        return (Some(value_to_string(example)), diagnostics);
+    }
+    if let Some(default) = &schema.schema_data.default {
+        return (Some(value_to_string(default)), diagnostics);
+    }
+    let value = match &schema.schema_kind {
+        SchemaKind::Type(Type::String(s)) => s.enumeration.iter().flatten().next().cloned(),
+        SchemaKind::Type(Type::Integer(i)) => {
+            i.enumeration.iter().flatten().next().map(|v| v.to_string())
+        }
+        SchemaKind::Type(Type::Number(n)) => {
+            n.enumeration.iter().flatten().next().map(|v| v.to_string())
+        }
+        _ => None,
+    };
+    (value, diagnostics)
+}
+
+/// Strings are written without their quotes, everything else as JSON.
+fn value_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}